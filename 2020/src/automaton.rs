@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+use std::iter;
+
+/// A position in an `N`-dimensional lattice.
+pub type Point<const N: usize> = [i32; N];
+
+/// The state of a single cell. The `Default` state is the background state that fills
+/// everything outside of a dense grid and everything that is not stored in a sparse one.
+pub trait State: Copy + Eq + Hash + Default {}
+
+impl<T: Copy + Eq + Hash + Default> State for T {}
+
+/// Determines which cells influence the next state of a cell.
+pub trait Neighbourhood<const N: usize> {
+    fn neighbours(&self, p: Point<N>, out: &mut Vec<Point<N>>);
+}
+
+/// A neighbourhood described by fixed offsets relative to the cell.
+#[derive(Debug, Clone)]
+pub struct Offsets<const N: usize>(Vec<Point<N>>);
+
+impl<const N: usize> Offsets<N> {
+    /// All cells within a Chebyshev distance of 1, i.e. 8 in 2D, 26 in 3D and 80 in 4D.
+    pub fn moore() -> Self {
+        let offsets = (0..3usize.pow(N as u32))
+            .map(|mut n| {
+                let mut offset = [0; N];
+                for x in offset.iter_mut() {
+                    *x = (n % 3) as i32 - 1;
+                    n /= 3;
+                }
+                offset
+            })
+            .filter(|offset| offset.iter().any(|&x| x != 0))
+            .collect();
+
+        Self(offsets)
+    }
+}
+
+impl<const N: usize> Neighbourhood<N> for Offsets<N> {
+    fn neighbours(&self, p: Point<N>, out: &mut Vec<Point<N>>) {
+        out.extend(self.0.iter().map(|offset| {
+            let mut q = p;
+            q.iter_mut().zip(offset).for_each(|(x, dx)| *x += dx);
+            q
+        }));
+    }
+}
+
+/// A neighbourhood computed up front, for when it depends on more than relative offsets.
+/// Cells that are missing from the table have no neighbours.
+#[derive(Debug, Clone, Default)]
+pub struct Table<const N: usize>(HashMap<Point<N>, Vec<Point<N>>>);
+
+impl<const N: usize> Table<N> {
+    pub fn insert(&mut self, p: Point<N>, neighbours: Vec<Point<N>>) {
+        self.0.insert(p, neighbours);
+    }
+}
+
+impl<const N: usize> Neighbourhood<N> for Table<N> {
+    fn neighbours(&self, p: Point<N>, out: &mut Vec<Point<N>>) {
+        if let Some(neighbours) = self.0.get(&p) {
+            out.extend_from_slice(neighbours);
+        }
+    }
+}
+
+/// Computes the next state of a cell from its current state and those of its neighbours.
+pub trait Rule<S> {
+    fn apply(&self, cell: S, neighbours: &[S]) -> S;
+}
+
+impl<S, F: Fn(S, &[S]) -> S> Rule<S> for F {
+    fn apply(&self, cell: S, neighbours: &[S]) -> S {
+        self(cell, neighbours)
+    }
+}
+
+/// Storage for the cells of a generation.
+pub trait Backend<S: State, const N: usize>: Clone + Eq + Hash {
+    fn get(&self, p: Point<N>) -> S;
+
+    /// All cells whose state may differ from the background in the next generation.
+    fn candidates<Nb: Neighbourhood<N>>(&self, nb: &Nb) -> Vec<Point<N>>;
+
+    /// An empty grid of the same shape.
+    fn blank(&self) -> Self;
+
+    fn set(&mut self, p: Point<N>, state: S);
+
+    /// Cells that are not in the background state.
+    fn cells(&self) -> Vec<(Point<N>, S)>;
+
+    /// How many cells are in the given state. An unbounded grid has no end of background
+    /// cells, so unless overridden only the cells that are not in the background are counted.
+    fn count(&self, state: S) -> usize {
+        self.cells().iter().filter(|(_, s)| *s == state).count()
+    }
+
+    fn step<Nb: Neighbourhood<N>, R: Rule<S>>(&self, nb: &Nb, rule: &R) -> Self {
+        let mut next = self.blank();
+        let mut points = Vec::new();
+        let mut states = Vec::new();
+
+        for p in self.candidates(nb) {
+            points.clear();
+            nb.neighbours(p, &mut points);
+
+            states.clear();
+            states.extend(points.iter().map(|&q| self.get(q)));
+
+            let state = rule.apply(self.get(p), &states);
+            if state != S::default() {
+                next.set(p, state);
+            }
+        }
+
+        next
+    }
+}
+
+/// A bounded grid stored in a flat `Vec`, for puzzles on a fixed map.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dense<S, const N: usize> {
+    dims: [usize; N],
+    cells: Vec<S>,
+}
+
+impl<S: State, const N: usize> Dense<S, N> {
+    pub fn new(dims: [usize; N]) -> Self {
+        Self {
+            dims,
+            cells: vec![S::default(); dims.iter().product()],
+        }
+    }
+
+    pub fn dims(&self) -> [usize; N] {
+        self.dims
+    }
+
+    pub fn contains(&self, p: Point<N>) -> bool {
+        self.index(p).is_some()
+    }
+
+    fn index(&self, p: Point<N>) -> Option<usize> {
+        p.iter().zip(&self.dims).try_fold(0, |index, (&x, &dim)| {
+            (x >= 0 && (x as usize) < dim).then(|| index * dim + x as usize)
+        })
+    }
+
+    fn point(&self, mut index: usize) -> Point<N> {
+        let mut p = [0; N];
+        for (x, &dim) in p.iter_mut().zip(&self.dims).rev() {
+            *x = (index % dim) as i32;
+            index /= dim;
+        }
+        p
+    }
+}
+
+impl<S: State, const N: usize> Backend<S, N> for Dense<S, N> {
+    /// Cells outside of the grid are always in the background state.
+    fn get(&self, p: Point<N>) -> S {
+        self.index(p).map_or_else(S::default, |i| self.cells[i])
+    }
+
+    fn candidates<Nb: Neighbourhood<N>>(&self, _nb: &Nb) -> Vec<Point<N>> {
+        (0..self.cells.len()).map(|i| self.point(i)).collect()
+    }
+
+    fn blank(&self) -> Self {
+        Self::new(self.dims)
+    }
+
+    /// Panics if the point lies outside of the grid.
+    fn set(&mut self, p: Point<N>, state: S) {
+        let i = self.index(p).expect("Point outside of dense grid");
+        self.cells[i] = state;
+    }
+
+    fn count(&self, state: S) -> usize {
+        self.cells.iter().filter(|&&s| s == state).count()
+    }
+
+    fn cells(&self) -> Vec<(Point<N>, S)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &s)| s != S::default())
+            .map(|(i, &s)| (self.point(i), s))
+            .collect()
+    }
+}
+
+/// An unbounded grid that only stores cells which are not in the background state,
+/// for puzzles where the active region keeps growing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Sparse<S, const N: usize> {
+    cells: BTreeMap<Point<N>, S>,
+}
+
+impl<S: State, const N: usize> Sparse<S, N> {
+    pub fn new() -> Self {
+        Self {
+            cells: BTreeMap::new(),
+        }
+    }
+}
+
+impl<S: State, const N: usize> Backend<S, N> for Sparse<S, N> {
+    fn get(&self, p: Point<N>) -> S {
+        self.cells.get(&p).copied().unwrap_or_default()
+    }
+
+    /// Assumes a background cell surrounded by background cells stays in the background.
+    fn candidates<Nb: Neighbourhood<N>>(&self, nb: &Nb) -> Vec<Point<N>> {
+        let mut candidates = BTreeSet::new();
+        let mut points = Vec::new();
+
+        for &p in self.cells.keys() {
+            points.clear();
+            nb.neighbours(p, &mut points);
+            candidates.extend(iter::once(p).chain(points.iter().copied()));
+        }
+
+        candidates.into_iter().collect()
+    }
+
+    fn blank(&self) -> Self {
+        Self::new()
+    }
+
+    fn set(&mut self, p: Point<N>, state: S) {
+        if state == S::default() {
+            self.cells.remove(&p);
+        } else {
+            self.cells.insert(p, state);
+        }
+    }
+
+    fn cells(&self) -> Vec<(Point<N>, S)> {
+        self.cells.iter().map(|(&p, &s)| (p, s)).collect()
+    }
+}
+
+pub struct Automaton<S, B, Nb, R, const N: usize> {
+    grid: B,
+    neighbourhood: Nb,
+    rule: R,
+    generation: usize,
+    _state: std::marker::PhantomData<S>,
+}
+
+impl<S, B, Nb, R, const N: usize> Automaton<S, B, Nb, R, N>
+where
+    S: State,
+    B: Backend<S, N>,
+    Nb: Neighbourhood<N>,
+    R: Rule<S>,
+{
+    pub fn new(grid: B, neighbourhood: Nb, rule: R) -> Self {
+        Self {
+            grid,
+            neighbourhood,
+            rule,
+            generation: 0,
+            _state: std::marker::PhantomData,
+        }
+    }
+
+    pub fn grid(&self) -> &B {
+        &self.grid
+    }
+
+    /// Advances one generation and returns whether any cell changed.
+    pub fn step(&mut self) -> bool {
+        let next = self.grid.step(&self.neighbourhood, &self.rule);
+        let changed = next != self.grid;
+
+        self.grid = next;
+        self.generation += 1;
+
+        changed
+    }
+
+    /// Steps until the grid no longer changes, showing every generation to `observe`, and
+    /// returns the generation it stabilised at.
+    pub fn run_until_stable<E>(
+        &mut self,
+        mut observe: impl FnMut(&B) -> Result<(), E>,
+    ) -> Result<usize, E> {
+        observe(&self.grid)?;
+        while self.step() {
+            observe(&self.grid)?;
+        }
+
+        Ok(self.generation - 1)
+    }
+
+    /// Runs up to `generation`, skipping ahead once the grid stops changing. Returns the
+    /// generation it stabilised at, if it did.
+    pub fn run(&mut self, generation: usize) -> Option<usize> {
+        while self.generation < generation {
+            if !self.step() {
+                let stable = self.generation - 1;
+                self.generation = generation;
+
+                return Some(stable);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(alive: bool, neighbours: &[bool]) -> bool {
+        let n = neighbours.iter().filter(|&&x| x).count();
+        n == 3 || (alive && n == 2)
+    }
+
+    fn sparse<const N: usize>(points: &[Point<N>]) -> Sparse<bool, N> {
+        let mut grid = Sparse::new();
+        for &p in points {
+            grid.set(p, true);
+        }
+        grid
+    }
+
+    fn count<const N: usize>(nb: impl Neighbourhood<N>) -> usize {
+        let mut out = Vec::new();
+        nb.neighbours([0; N], &mut out);
+        out.len()
+    }
+
+    #[test]
+    fn neighbourhood_sizes() {
+        assert_eq!(count(Offsets::<2>::moore()), 8);
+        assert_eq!(count(Offsets::<3>::moore()), 26);
+        assert_eq!(count(Offsets::<4>::moore()), 80);
+    }
+
+    #[test]
+    fn block_is_still() {
+        let block = sparse(&[[0, 0], [0, 1], [1, 0], [1, 1]]);
+
+        let mut automaton = Automaton::new(block.clone(), Offsets::moore(), life);
+        assert_eq!(automaton.run_until_stable(|_| Ok::<_, ()>(())), Ok(0));
+
+        let mut automaton = Automaton::new(block.clone(), Offsets::moore(), life);
+        assert_eq!(automaton.run(50), Some(0));
+        assert_eq!(automaton.grid(), &block);
+    }
+
+    #[test]
+    fn blinker_never_stabilises() {
+        let horizontal = sparse(&[[0, -1], [0, 0], [0, 1]]);
+        let vertical = sparse(&[[-1, 0], [0, 0], [1, 0]]);
+
+        let mut automaton = Automaton::new(horizontal, Offsets::moore(), life);
+        assert_eq!(automaton.run(51), None);
+        assert_eq!(automaton.grid(), &vertical);
+    }
+
+    #[test]
+    fn counts() {
+        let mut dense = Dense::new([3, 4]);
+        dense.set([1, 2], true);
+        dense.set([2, 0], true);
+
+        assert_eq!(dense.count(true), 2);
+        assert_eq!(dense.count(false), 10);
+
+        // Only the cells outside the background are stored
+        let sparse = sparse(&[[1, 2], [2, 0]]);
+        assert_eq!(sparse.count(true), 2);
+        assert_eq!(sparse.count(false), 0);
+    }
+}
@@ -1,32 +1,210 @@
-use anyhow::Context;
+use anyhow::{bail, Context};
 
-use std::collections::HashSet;
-use std::iter::FromIterator;
+use std::cmp::Ordering;
 
-pub fn part1(input: &str) -> anyhow::Result<u32> {
-    let xs = HashSet::<u32>::from_iter(
-        input
-            .lines()
-            .map(|s| s.parse::<u32>().expect("Could not parse value")),
-    );
+/// Finds tuples of `k` entries that sum to `target`, as indices into `xs`, in ascending order
+/// of value. Every combination of values is reported once, even if some of the values appear
+/// more than once, and the search stops at the first tuple if `first_only` is set.
+pub fn k_sum(xs: &[i64], target: i64, k: usize, first_only: bool) -> Vec<Vec<usize>> {
+    let mut sorted = xs
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| (x, i))
+        .collect::<Vec<_>>();
+    sorted.sort_unstable();
 
-    xs.iter()
-        .find_map(|x| xs.get(&(2020 - x)).map(|y| x * y))
-        .context("No solution was found")
+    let mut found = Vec::new();
+    if k > 0 {
+        search(
+            &sorted,
+            target as i128,
+            k,
+            &mut Vec::new(),
+            &mut found,
+            first_only,
+        );
+    }
+
+    found
+}
+
+/// Returns whether to stop searching
+fn search(
+    sorted: &[(i64, usize)],
+    target: i128,
+    k: usize,
+    prefix: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+    first_only: bool,
+) -> bool {
+    if sorted.len() < k {
+        return false;
+    }
+
+    // The smallest and largest sums of k entries bound what can be reached
+    let smallest = sorted[..k].iter().map(|&(x, _)| x as i128).sum::<i128>();
+    let largest = sorted[sorted.len() - k..]
+        .iter()
+        .map(|&(x, _)| x as i128)
+        .sum::<i128>();
+    if target < smallest || target > largest {
+        return false;
+    }
+
+    let report = |found: &mut Vec<Vec<usize>>, prefix: &[usize], indices: &[usize]| {
+        found.push(prefix.iter().chain(indices).copied().collect());
+        first_only
+    };
+
+    match k {
+        1 => match sorted.binary_search_by(|&(x, _)| (x as i128).cmp(&target)) {
+            Ok(i) => report(found, prefix, &[sorted[i].1]),
+            Err(_) => false,
+        },
+        2 => {
+            let (mut lo, mut hi) = (0, sorted.len() - 1);
+
+            while lo < hi {
+                let (a, b) = (sorted[lo], sorted[hi]);
+
+                match (a.0 as i128 + b.0 as i128).cmp(&target) {
+                    Ordering::Less => lo += 1,
+                    Ordering::Greater => hi -= 1,
+                    Ordering::Equal => {
+                        if report(found, prefix, &[a.1, b.1]) {
+                            return true;
+                        }
+
+                        // Skip over the same values, so each pair is reported once
+                        while lo < hi && sorted[lo].0 == a.0 {
+                            lo += 1;
+                        }
+                        while lo < hi && sorted[hi].0 == b.0 {
+                            hi -= 1;
+                        }
+                    }
+                }
+            }
+
+            false
+        }
+        _ => {
+            for i in 0..=sorted.len() - k {
+                if i > 0 && sorted[i].0 == sorted[i - 1].0 {
+                    continue;
+                }
+
+                let (x, index) = sorted[i];
+                prefix.push(index);
+                let stop = search(
+                    &sorted[i + 1..],
+                    target - x as i128,
+                    k - 1,
+                    prefix,
+                    found,
+                    first_only,
+                );
+                prefix.pop();
+
+                if stop {
+                    return true;
+                }
+            }
+
+            false
+        }
+    }
 }
 
-pub fn part2(input: &str) -> anyhow::Result<i32> {
-    let xs = HashSet::<i32>::from_iter(
-        input
-            .lines()
-            .map(|s| s.parse::<i32>().expect("Could not parse value")),
-    );
-
-    xs.iter()
-        .find_map(|x| {
-            xs.iter()
-                .filter(|&y| x != y)
-                .find_map(|y| xs.get(&(2020 - x - y)).map(|z| x * y * z))
+fn parse_entries(input: &str) -> anyhow::Result<Vec<i64>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
         })
-        .context("No solution was found")
+        .collect()
+}
+
+/// Multiplies the entries of the first tuple that sums to the target, printing every tuple
+/// with the lines of its entries if asked to
+fn solve(input: &str, target: i64, k: usize, all: bool) -> anyhow::Result<i64> {
+    if k == 0 {
+        bail!("Need at least one entry to sum");
+    }
+
+    let xs = parse_entries(input)?;
+    let tuples = k_sum(&xs, target, k, !all);
+
+    if all {
+        for tuple in &tuples {
+            let terms = tuple
+                .iter()
+                .map(|&i| format!("{} (line {})", xs[i], i + 1))
+                .collect::<Vec<_>>();
+            println!("{} = {}", terms.join(" + "), target);
+        }
+    }
+
+    let tuple = tuples
+        .first()
+        .with_context(|| format!("No {} entries sum to {}", k, target))?;
+
+    tuple
+        .iter()
+        .try_fold(1i64, |product, &i| product.checked_mul(xs[i]))
+        .context("The product of the entries overflows")
+}
+
+pub fn part1(input: &str, target: i64, k: Option<usize>, all: bool) -> anyhow::Result<i64> {
+    solve(input, target, k.unwrap_or(2), all)
+}
+
+pub fn part2(input: &str, target: i64, k: Option<usize>, all: bool) -> anyhow::Result<i64> {
+    solve(input, target, k.unwrap_or(3), all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1721\n979\n366\n299\n675\n1456\n";
+
+    /// The values of every tuple, for comparing without caring about indices
+    fn values(xs: &[i64], target: i64, k: usize) -> Vec<Vec<i64>> {
+        k_sum(xs, target, k, false)
+            .iter()
+            .map(|tuple| tuple.iter().map(|&i| xs[i]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE, 2020, None, false).unwrap(), 514579);
+        assert_eq!(part2(EXAMPLE, 2020, None, false).unwrap(), 241861950);
+    }
+
+    #[test]
+    fn every_tuple_once() {
+        let xs = [1, 3, 2, 2, 3, 1];
+        assert_eq!(values(&xs, 4, 2), vec![vec![1, 3], vec![2, 2]]);
+        assert_eq!(values(&xs, 6, 3), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn negative_target() {
+        let xs = [-5, 3, 10, -7, 1];
+        assert_eq!(values(&xs, -2, 2), vec![vec![-5, 3]]);
+        assert_eq!(values(&xs, -11, 3), vec![vec![-7, -5, 1]]);
+        assert_eq!(part1("-5\n3\n10\n", -2, None, false).unwrap(), -15);
+    }
+
+    #[test]
+    fn more_entries_than_input() {
+        assert!(values(&[1, 2, 3], 6, 4).is_empty());
+        assert!(part1("1\n2\n3\n", 6, Some(4), false).is_err());
+        assert!(part1("1\n2\n3\n", 6, Some(0), false).is_err());
+    }
 }
@@ -1,57 +1,257 @@
-// use anyhow::Context;
+use anyhow::{bail, Context};
+use num_bigint::BigUint;
 
-use itertools::Itertools;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let mut xs = input
-        .lines()
-        .map(|l| l.parse::<isize>().unwrap())
-        .collect::<Vec<_>>();
+/// The joltages of a chain of adapters, from the outlet at zero up to the device, which is
+/// rated the largest allowed gap above the highest adapter
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    gaps: BTreeSet<u64>,
+    joltages: Vec<u64>,
+    /// Ways to reach each joltage from the outlet
+    ways: Vec<BigUint>,
+}
+
+impl Analysis {
+    pub fn new(adapters: &[u64], gaps: &BTreeSet<u64>) -> anyhow::Result<Self> {
+        let max_gap = *gaps.iter().next_back().context("No gaps are allowed")?;
+
+        let mut joltages = adapters.to_vec();
+        joltages.push(0);
+        joltages.sort_unstable();
+        let device = joltages[joltages.len() - 1] + max_gap;
+        joltages.push(device);
+
+        if let Some(w) = joltages.windows(2).find(|w| w[1] - w[0] > max_gap) {
+            bail!(
+                "The chain is broken between {} and {}: a gap of {} is larger than {}",
+                w[0],
+                w[1],
+                w[1] - w[0],
+                max_gap
+            );
+        }
+
+        let mut ways = vec![BigUint::default(); joltages.len()];
+        ways[0] = BigUint::from(1u32);
 
-    xs.sort_unstable();
+        for i in 1..joltages.len() {
+            let mut total = BigUint::default();
+            for j in (0..i).rev() {
+                let gap = joltages[i] - joltages[j];
+                if gap > max_gap {
+                    break;
+                }
+                if gaps.contains(&gap) {
+                    total += &ways[j];
+                }
+            }
+            ways[i] = total;
+        }
+
+        Ok(Self {
+            gaps: gaps.clone(),
+            joltages,
+            ways,
+        })
+    }
 
-    let mut count = HashMap::<isize, usize>::new();
+    /// How often each gap occurs when every adapter is used
+    pub fn histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram = BTreeMap::new();
 
-    for (a, b) in std::iter::once(0).chain(xs.into_iter()).tuple_windows() {
-        *count.entry(b - a).or_default() += 1;
+        for w in self.joltages.windows(2) {
+            *histogram.entry(w[1] - w[0]).or_insert(0) += 1;
+        }
+
+        histogram
     }
 
-    let result = count.get(&1).unwrap() * (count.get(&3).unwrap() + 1);
+    /// Gaps in the chain of every adapter that are not allowed
+    pub fn invalid_gaps(&self) -> Vec<(u64, u64)> {
+        self.joltages
+            .windows(2)
+            .filter(|w| !self.gaps.contains(&(w[1] - w[0])))
+            .map(|w| (w[0], w[1]))
+            .collect()
+    }
 
-    Ok(result)
-}
+    /// Distinct ways to connect the outlet to the device
+    pub fn arrangements(&self) -> &BigUint {
+        &self.ways[self.ways.len() - 1]
+    }
+
+    /// Every arrangement, from the outlet to the device, generated lazily
+    pub fn iter(&self) -> Arrangements<'_> {
+        let mut arrangements = Arrangements {
+            analysis: self,
+            reaches_device: vec![false; self.joltages.len()],
+            stack: Vec::new(),
+        };
+
+        // Only adapters from which the device can be reached are worth visiting
+        let last = self.joltages.len() - 1;
+        arrangements.reaches_device[last] = true;
+        for i in (0..last).rev() {
+            arrangements.reaches_device[i] =
+                self.successors(i).any(|j| arrangements.reaches_device[j]);
+        }
+
+        if arrangements.reaches_device[0] {
+            arrangements.stack.push((0, self.successors_reversed(0)));
+        }
+
+        arrangements
+    }
+
+    /// A chain with as few adapters as possible, if there is one
+    pub fn shortest_chain(&self) -> Option<Vec<u64>> {
+        let mut steps = vec![None; self.joltages.len()];
+        let mut previous = vec![0; self.joltages.len()];
+        steps[0] = Some(0);
 
-fn find_paths(xs: &HashSet<isize>, mut memo: &mut HashMap<isize, isize>, x: isize) -> isize {
-    let mut paths = 0;
+        for i in 0..self.joltages.len() {
+            let step = match steps[i] {
+                Some(step) => step + 1,
+                None => continue,
+            };
 
-    for i in 1..=3 {
-        if let Some(count) = memo.get(&(x - i)) {
-            paths += count;
-        } else if xs.contains(&(x - i)) {
-            let count = find_paths(xs, &mut memo, x - i);
-            memo.insert(x - i, count);
-            paths += count;
+            for j in self.successors(i) {
+                if steps[j].is_none_or(|s| step < s) {
+                    steps[j] = Some(step);
+                    previous[j] = i;
+                }
+            }
         }
+
+        let mut i = self.joltages.len() - 1;
+        steps[i]?;
+
+        let mut chain = vec![self.joltages[i]];
+        while i > 0 {
+            i = previous[i];
+            chain.push(self.joltages[i]);
+        }
+        chain.reverse();
+
+        Some(chain)
     }
 
-    if x - 3 <= 0 {
-        paths += 1;
+    /// Indices of the adapters that can follow the one at `i`
+    fn successors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let max_gap = *self.gaps.iter().next_back().unwrap();
+
+        (i + 1..self.joltages.len())
+            .take_while(move |&j| self.joltages[j] - self.joltages[i] <= max_gap)
+            .filter(move |&j| self.gaps.contains(&(self.joltages[j] - self.joltages[i])))
     }
 
-    paths
+    /// Reversed, so popping them tries the lowest joltage first
+    fn successors_reversed(&self, i: usize) -> Vec<usize> {
+        let mut successors = self.successors(i).collect::<Vec<_>>();
+        successors.reverse();
+        successors
+    }
+}
+
+/// A depth-first walk over the arrangements of an [`Analysis`]
+pub struct Arrangements<'a> {
+    analysis: &'a Analysis,
+    reaches_device: Vec<bool>,
+    /// The adapters of the current chain, each with the successors still to try
+    stack: Vec<(usize, Vec<usize>)>,
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.analysis.joltages.len() - 1;
+
+        while let Some((_, next)) = self.stack.last_mut() {
+            let j = match next.pop() {
+                Some(j) => j,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            if !self.reaches_device[j] {
+                continue;
+            }
+
+            if j == last {
+                let mut chain = self
+                    .stack
+                    .iter()
+                    .map(|&(i, _)| self.analysis.joltages[i])
+                    .collect::<Vec<_>>();
+                chain.push(self.analysis.joltages[j]);
+                return Some(chain);
+            }
+
+            self.stack.push((j, self.analysis.successors_reversed(j)));
+        }
+
+        None
+    }
 }
 
-pub fn part2(input: &str) -> anyhow::Result<isize> {
-    let xs = input
+fn parse_adapters(input: &str) -> anyhow::Result<Vec<u64>> {
+    input
         .lines()
-        .map(|l| l.parse::<isize>().unwrap())
-        .collect::<HashSet<_>>();
-    let max = xs.iter().max().copied().unwrap();
-    let mut memo = HashMap::<isize, isize>::new();
+        .enumerate()
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
+        })
+        .collect()
+}
 
-    let count = find_paths(&xs, &mut memo, max);
+fn analyze(input: &str, gaps: &[u64]) -> anyhow::Result<Analysis> {
+    let gaps = if gaps.is_empty() {
+        (1..=3).collect()
+    } else {
+        gaps.iter().copied().collect()
+    };
+
+    Analysis::new(&parse_adapters(input)?, &gaps)
+}
+
+/// Uses every adapter, so each gap in the chain has to be allowed
+pub fn part1(input: &str, gaps: &[u64]) -> anyhow::Result<usize> {
+    let analysis = analyze(input, gaps)?;
+
+    if let Some(&(from, to)) = analysis.invalid_gaps().first() {
+        bail!(
+            "Using every adapter, the gap of {} between {} and {} is not allowed",
+            to - from,
+            from,
+            to
+        );
+    }
+
+    let histogram = analysis.histogram();
+    let count = |gap| histogram.get(&gap).copied().unwrap_or(0);
+
+    Ok(count(1) * count(3))
+}
+
+/// Prints the shortest chain and every arrangement if asked to
+pub fn part2(input: &str, gaps: &[u64], all: bool) -> anyhow::Result<BigUint> {
+    let analysis = analyze(input, gaps)?;
+
+    if all {
+        if let Some(chain) = analysis.shortest_chain() {
+            println!("shortest: {:?}", chain);
+        }
+        for arrangement in analysis.iter() {
+            println!("{:?}", arrangement);
+        }
+    }
 
-    Ok(count)
+    Ok(analysis.arrangements().clone())
 }
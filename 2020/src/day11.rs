@@ -1,100 +1,129 @@
-#[derive(Debug, Clone, PartialEq)]
-struct Seats {
-    grid: Vec<Vec<Cell>>,
-    rows: usize,
-    cols: usize,
-}
+use anyhow::{bail, Context};
+
+use crate::automaton::{Automaton, Backend, Dense, Neighbourhood, Offsets, Table};
+use crate::visualize::{Glyph, Visualizer};
+
+type Seats = Dense<Cell, 2>;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 enum Cell {
+    #[default]
     Floor,
     Empty,
     Occupied,
 }
 
-impl Seats {
-    fn new(s: &str) -> Self {
-        let rows = s.lines().count();
-        let cols = s.lines().nth(0).map(|l| l.chars().count()).unwrap();
-
-        let mut grid = vec![vec![Cell::Floor; cols + 2]; rows + 2];
-
-        for (i, row) in s.lines().enumerate() {
-            for (j, col) in row.char_indices() {
-                grid[i + 1][j + 1] = match col {
-                    'L' => Cell::Empty,
-                    '.' => Cell::Floor,
-                    _ => panic!("Invalid char: \"{:?}\"", col),
-                }
-            }
+impl Glyph for Cell {
+    fn glyph(&self) -> char {
+        match self {
+            Cell::Floor => '.',
+            Cell::Empty => 'L',
+            Cell::Occupied => '#',
         }
-
-        Self { grid, rows, cols }
     }
 
-    fn get_neigbors(&self, row: usize, col: usize) -> [Cell; 8] {
-        [
-            self.grid[row - 1][col - 1],
-            self.grid[row - 1][col],
-            self.grid[row - 1][col + 1],
-            self.grid[row][col - 1],
-            self.grid[row][col + 1],
-            self.grid[row + 1][col - 1],
-            self.grid[row + 1][col],
-            self.grid[row + 1][col + 1],
-        ]
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            Cell::Floor => [40, 40, 40],
+            Cell::Empty => [80, 200, 120],
+            Cell::Occupied => [220, 60, 60],
+        }
     }
+}
 
-    fn curr(&self) -> Vec<Vec<Cell>> {
-        self.grid.clone()
+fn parse_seats(s: &str) -> anyhow::Result<Seats> {
+    let rows = s.lines().count();
+    let cols = s
+        .lines()
+        .next()
+        .map(|l| l.chars().count())
+        .context("No seats were found")?;
+
+    let mut seats = Seats::new([rows, cols]);
+
+    for (i, row) in s.lines().enumerate() {
+        if row.chars().count() != cols {
+            bail!(
+                "Row {} has {} seats instead of {}",
+                i + 1,
+                row.chars().count(),
+                cols
+            );
+        }
+
+        for (j, col) in row.chars().enumerate() {
+            let cell = match col {
+                'L' => Cell::Empty,
+                '#' => Cell::Occupied,
+                '.' => Cell::Floor,
+                _ => bail!("Invalid char: \"{:?}\"", col),
+            };
+
+            seats.set([i as i32, j as i32], cell);
+        }
     }
 
-    fn count_occupied(&self) -> usize {
-        self.grid
+    Ok(seats)
+}
+
+/// For each seat, the first seat that can be seen in each of the eight directions
+fn visible_seats(seats: &Seats) -> Table<2> {
+    let mut directions = Vec::new();
+    Offsets::<2>::moore().neighbours([0, 0], &mut directions);
+
+    let mut table = Table::default();
+
+    for (p, _) in seats.cells() {
+        let visible = directions
             .iter()
-            .map(|row| row.iter().filter(|&x| *x == Cell::Occupied).count())
-            .sum()
+            .filter_map(|d| {
+                (1..)
+                    .map(|n| [p[0] + n * d[0], p[1] + n * d[1]])
+                    .take_while(|&q| seats.contains(q))
+                    .find(|&q| seats.get(q) != Cell::Floor)
+            })
+            .collect();
+
+        table.insert(p, visible);
     }
 
-    fn next(&mut self) -> Vec<Vec<Cell>> {
-        let mut next = vec![vec![Cell::Floor; self.grid[0].len()]; self.grid.len()];
-
-        for (i, row) in self.grid.iter().enumerate().skip(1).take(self.rows) {
-            for (j, col) in row.iter().enumerate().skip(1).take(self.cols) {
-                let occupied = self
-                    .get_neigbors(i, j)
-                    .iter()
-                    .filter(|&x| *x == Cell::Occupied)
-                    .count();
-
-                next[i][j] = match *col {
-                    Cell::Empty if occupied == 0 => Cell::Occupied,
-                    Cell::Occupied if occupied >= 4 => Cell::Empty,
-                    v => v,
-                }
-            }
-        }
+    table
+}
 
-        self.grid = next;
-        self.grid.clone()
+fn seat_rule(tolerance: usize) -> impl Fn(Cell, &[Cell]) -> Cell {
+    move |cell, neighbours| {
+        let occupied = neighbours.iter().filter(|&&x| x == Cell::Occupied).count();
+
+        match cell {
+            Cell::Empty if occupied == 0 => Cell::Occupied,
+            Cell::Occupied if occupied >= tolerance => Cell::Empty,
+            v => v,
+        }
     }
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let mut seats = Seats::new(input);
-
-    // use std::time::Duration;
-    // let now = std::time::Instant::now();
+fn count_stable<Nb: Neighbourhood<2>>(
+    seats: Seats,
+    neighbourhood: Nb,
+    tolerance: usize,
+    visualizer: &mut Visualizer,
+) -> anyhow::Result<usize> {
+    let mut automaton = Automaton::new(seats, neighbourhood, seat_rule(tolerance));
 
-    while seats.curr().clone() != seats.next() {}
+    automaton.run_until_stable(|seats| visualizer.frame(seats))?;
 
-    let occupied = seats.count_occupied();
+    Ok(automaton.grid().count(Cell::Occupied))
+}
 
-    // println!("time: {:?}", now.elapsed().as_millis());
+pub fn part1(input: &str, visualizer: &mut Visualizer) -> anyhow::Result<usize> {
+    let seats = parse_seats(input)?;
 
-    Ok(occupied)
+    count_stable(seats, Offsets::moore(), 4, visualizer)
 }
 
-pub fn part2(_input: &str) -> anyhow::Result<i32> {
-    todo!()
+pub fn part2(input: &str, visualizer: &mut Visualizer) -> anyhow::Result<usize> {
+    let seats = parse_seats(input)?;
+    let visible = visible_seats(&seats);
+
+    count_stable(seats, visible, 5, visualizer)
 }
@@ -0,0 +1,87 @@
+use anyhow::{bail, Context};
+
+use crate::geometry::{Direction, Vec2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Move(Direction, i64),
+    /// Quarter turns, clockwise if positive
+    Turn(i64),
+    Forward(i64),
+}
+
+/// Parses an action such as `N3`, `R90` or `F10`
+fn parse_action(s: &str) -> anyhow::Result<Action> {
+    let s = s.trim();
+    let action = s.chars().next().context("Expected an action")?;
+    let value = &s[action.len_utf8()..];
+    let value = value
+        .parse::<i64>()
+        .with_context(|| format!("Invalid value '{}'", value))?;
+
+    let turn = |degrees: i64| {
+        if degrees % 90 != 0 {
+            bail!("Can only turn by multiples of 90 degrees, not {}", degrees);
+        }
+
+        Ok(Action::Turn(degrees / 90))
+    };
+
+    match action {
+        'N' => Ok(Action::Move(Direction::North, value)),
+        'E' => Ok(Action::Move(Direction::East, value)),
+        'S' => Ok(Action::Move(Direction::South, value)),
+        'W' => Ok(Action::Move(Direction::West, value)),
+        'L' => turn(-value),
+        'R' => turn(value),
+        'F' => Ok(Action::Forward(value)),
+        c => bail!(
+            "Unknown action '{}'; must be one of N, E, S, W, L, R or F",
+            c
+        ),
+    }
+}
+
+fn parse_actions(input: &str) -> anyhow::Result<Vec<Action>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            parse_action(line)
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
+        })
+        .collect()
+}
+
+/// The ship moves and turns itself, starting out facing east
+pub fn part1(input: &str) -> anyhow::Result<i64> {
+    let mut position = Vec2::ZERO;
+    let mut heading = Direction::East;
+
+    for action in parse_actions(input)? {
+        match action {
+            Action::Move(direction, distance) => position += direction.unit() * distance,
+            Action::Turn(quarter_turns) => heading = heading.rotate(quarter_turns),
+            Action::Forward(distance) => position += heading.unit() * distance,
+        }
+    }
+
+    Ok(position.manhattan())
+}
+
+/// The ship moves towards a waypoint, which starts out 10 east and 1 north of it, and every
+/// other action moves or rotates the waypoint around the ship
+pub fn part2(input: &str) -> anyhow::Result<i64> {
+    let mut position = Vec2::ZERO;
+    let mut waypoint = Vec2::new(10, 1);
+
+    for action in parse_actions(input)? {
+        match action {
+            Action::Move(direction, distance) => waypoint += direction.unit() * distance,
+            Action::Turn(quarter_turns) => waypoint = waypoint.rotate(quarter_turns),
+            Action::Forward(times) => position += waypoint * times,
+        }
+    }
+
+    Ok(position.manhattan())
+}
@@ -0,0 +1,181 @@
+use anyhow::{bail, Context};
+
+use std::str::FromStr;
+
+/// The buses in service, as pairs of offset in the list and bus id, skipping the `x` entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    buses: Vec<(i128, i128)>,
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    /// Parses a list such as `17,x,13,19`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buses = Vec::new();
+
+        for (offset, bus) in s.trim().split(',').enumerate() {
+            let bus = bus.trim();
+            if bus == "x" {
+                continue;
+            }
+
+            let id = bus
+                .parse::<i128>()
+                .with_context(|| format!("Invalid bus '{}'; must be an id or x", bus))?;
+            if id <= 0 {
+                bail!("Bus ids have to be positive, not {}", id);
+            }
+
+            buses.push((offset as i128, id));
+        }
+
+        if buses.is_empty() {
+            bail!("No buses are in service");
+        }
+
+        Ok(Self { buses })
+    }
+}
+
+/// The earliest timestamp and the buses, unless others were given
+fn parse_notes(input: &str, buses: Option<&Schedule>) -> anyhow::Result<(i128, Schedule)> {
+    let mut lines = input.lines();
+
+    let timestamp = lines.next().unwrap_or_default().trim();
+    let timestamp = timestamp
+        .parse()
+        .with_context(|| format!("Invalid timestamp on line 1: '{}'", timestamp))?;
+
+    let schedule = match buses {
+        Some(schedule) => schedule.clone(),
+        None => lines
+            .next()
+            .context("Expected the buses on line 2")?
+            .parse()
+            .context("Failed to parse line 2")?,
+    };
+
+    Ok((timestamp, schedule))
+}
+
+/// The greatest common divisor of `a` and `b`, and `x` such that `a * x` is that divisor
+/// modulo `b`
+fn extended_gcd(a: i128, b: i128) -> (i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+    }
+
+    (old_r, old_x)
+}
+
+/// The smallest non-negative `t` with `t ≡ residue (mod modulus)` for every pair, combining
+/// the congruences one at a time. The moduli do not have to be coprime.
+fn chinese_remainder(congruences: &[(i128, i128)]) -> anyhow::Result<i128> {
+    let (mut t, mut step) = (0i128, 1i128);
+
+    for &(residue, modulus) in congruences {
+        let residue = residue.rem_euclid(modulus);
+
+        // Solve t + step * k ≡ residue (mod modulus) for k
+        let (gcd, inverse) = extended_gcd(step, modulus);
+        let difference = residue - t.rem_euclid(modulus);
+        if difference % gcd != 0 {
+            bail!(
+                "No timestamp fits both every bus so far and bus {}",
+                modulus
+            );
+        }
+
+        let reduced = modulus / gcd;
+        let k = (difference / gcd)
+            .rem_euclid(reduced)
+            .checked_mul(inverse.rem_euclid(reduced))
+            .context("The timestamp is too large")?
+            .rem_euclid(reduced);
+
+        let lcm = step
+            .checked_mul(reduced)
+            .context("The timestamp is too large")?;
+        t = step
+            .checked_mul(k)
+            .and_then(|x| x.checked_add(t))
+            .context("The timestamp is too large")?
+            .rem_euclid(lcm);
+        step = lcm;
+    }
+
+    Ok(t)
+}
+
+/// Multiplies the id of the earliest bus with the minutes to wait for it
+pub fn part1(input: &str, buses: Option<&Schedule>) -> anyhow::Result<i128> {
+    let (timestamp, schedule) = parse_notes(input, buses)?;
+
+    let (wait, id) = schedule
+        .buses
+        .iter()
+        .map(|&(_, id)| ((-timestamp).rem_euclid(id), id))
+        .min()
+        .unwrap();
+
+    Ok(wait * id)
+}
+
+/// The earliest timestamp at which each bus departs as many minutes later as its offset in
+/// the list
+pub fn part2(input: &str, buses: Option<&Schedule>) -> anyhow::Result<i128> {
+    let schedule = match buses {
+        Some(schedule) => schedule.clone(),
+        None => parse_notes(input, None)?.1,
+    };
+
+    let congruences = schedule
+        .buses
+        .iter()
+        .map(|&(offset, id)| (-offset, id))
+        .collect::<Vec<_>>();
+
+    chinese_remainder(&congruences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn earliest(buses: &str) -> anyhow::Result<i128> {
+        part2("", Some(&buses.parse()?))
+    }
+
+    #[test]
+    fn gcd() {
+        assert_eq!(extended_gcd(240, 46), (2, -9));
+        assert_eq!(extended_gcd(17, 13).0, 1);
+        assert_eq!((17 * extended_gcd(17, 13).1).rem_euclid(13), 1);
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(part1("939\n7,13,x,x,59,x,31,19\n", None).unwrap(), 295);
+        assert_eq!(earliest("7,13,x,x,59,x,31,19").unwrap(), 1068781);
+        assert_eq!(earliest("17,x,13,19").unwrap(), 3417);
+        assert_eq!(earliest("67,7,59,61").unwrap(), 754018);
+        assert_eq!(earliest("1789,37,47,1889").unwrap(), 1202161486);
+    }
+
+    #[test]
+    fn not_coprime() {
+        // Every timestamp that suits bus 4 is even and every one that suits bus 6 odd
+        assert!(earliest("4,6").is_err());
+        assert!(chinese_remainder(&[(1, 6), (0, 4)]).is_err());
+
+        // The ids share a factor, but their offsets agree on it
+        assert_eq!(earliest("4,x,6").unwrap(), 4);
+    }
+}
@@ -0,0 +1,304 @@
+use anyhow::{bail, Context};
+
+use std::{collections::HashMap, mem};
+
+const BITS: usize = 36;
+
+/// A bitmask such as `XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X`, split by kind of bit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Mask {
+    ones: u64,
+    floating: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Mask(Mask),
+    Write { address: u64, value: u64 },
+}
+
+impl Mask {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        if s.len() != BITS {
+            bail!("Expected a mask of {} bits instead of {}", BITS, s.len());
+        }
+
+        s.chars().try_fold(Mask::default(), |mask, c| {
+            let (one, floating) = match c {
+                '0' => (0, 0),
+                '1' => (1, 0),
+                'X' => (0, 1),
+                c => bail!("Invalid bit '{}'; must be 0, 1 or X", c),
+            };
+
+            Ok(Mask {
+                ones: mask.ones << 1 | one,
+                floating: mask.floating << 1 | floating,
+            })
+        })
+    }
+
+    /// Ones and zeros overwrite the bits of the value, floating bits leave them as they are
+    fn apply_to_value(self, value: u64) -> u64 {
+        value & self.floating | self.ones
+    }
+
+    /// Ones overwrite the bits of the address, zeros leave them as they are and floating bits
+    /// take both values
+    fn apply_to_address(self, address: u64) -> Addresses {
+        Addresses {
+            fixed: (address | self.ones) & !self.floating,
+            floating: self.floating,
+        }
+    }
+}
+
+/// Parses a line such as `mask = 1X0...` or `mem[8] = 11`
+fn parse_instruction(line: &str) -> anyhow::Result<Instruction> {
+    let (target, value) = line
+        .split_once(" = ")
+        .context("Expected an assignment with ' = '")?;
+
+    if target == "mask" {
+        return Ok(Instruction::Mask(Mask::parse(value)?));
+    }
+
+    let address = target
+        .strip_prefix("mem[")
+        .and_then(|s| s.strip_suffix(']'))
+        .with_context(|| format!("Expected mask or mem[<address>] instead of '{}'", target))?;
+    let address = address
+        .parse::<u64>()
+        .with_context(|| format!("Invalid address '{}'", address))?;
+    let value = value
+        .parse::<u64>()
+        .with_context(|| format!("Invalid value '{}'", value))?;
+
+    if address >> BITS != 0 || value >> BITS != 0 {
+        bail!("Addresses and values have to fit in {} bits", BITS);
+    }
+
+    Ok(Instruction::Write { address, value })
+}
+
+fn parse_program(input: &str) -> anyhow::Result<Vec<Instruction>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            parse_instruction(line.trim())
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
+        })
+        .collect()
+}
+
+/// Every address that has the `fixed` bits outside of `floating`, whatever the floating
+/// bits are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Addresses {
+    fixed: u64,
+    floating: u64,
+}
+
+impl Addresses {
+    fn len(self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    fn intersects(self, other: Addresses) -> bool {
+        (self.fixed ^ other.fixed) & !self.floating & !other.floating == 0
+    }
+
+    /// The addresses of `self` that are not in `other`, as disjoint sets. Each bit that
+    /// floats here but is fixed in `other` splits off the addresses with the other value of
+    /// that bit, and is then fixed to the value of `other` for the rest.
+    fn subtract(self, other: Addresses, out: &mut Vec<Addresses>) {
+        if !self.intersects(other) {
+            out.push(self);
+            return;
+        }
+
+        let mut rest = self;
+        let mut split = self.floating & !other.floating;
+
+        while split != 0 {
+            let bit = split & split.wrapping_neg();
+            split &= !bit;
+            rest.floating &= !bit;
+
+            out.push(Addresses {
+                fixed: rest.fixed | (!other.fixed & bit),
+                floating: rest.floating,
+            });
+            rest.fixed |= other.fixed & bit;
+        }
+    }
+}
+
+/// Neither part has a mask that leaves writes as they are, so one has to be set first
+const NO_MASK: &str = "Memory is written before any mask is set";
+
+pub fn part1(input: &str) -> anyhow::Result<u64> {
+    let mut mask = None;
+    let mut memory = HashMap::new();
+
+    for instruction in parse_program(input)? {
+        match instruction {
+            Instruction::Mask(m) => mask = Some(m),
+            Instruction::Write { address, value } => {
+                let mask = mask.context(NO_MASK)?;
+                memory.insert(address, mask.apply_to_value(value));
+            }
+        }
+    }
+
+    Ok(memory.values().sum())
+}
+
+/// Memory is kept as disjoint sets of addresses with the value they hold, so a mask with many
+/// floating bits is a single write instead of one for every address
+pub fn part2(input: &str) -> anyhow::Result<u128> {
+    let mut mask = None;
+    let mut memory = Vec::<(Addresses, u64)>::new();
+    let mut pieces = Vec::new();
+
+    for instruction in parse_program(input)? {
+        match instruction {
+            Instruction::Mask(m) => mask = Some(m),
+            Instruction::Write { address, value } => {
+                let addresses = mask.context(NO_MASK)?.apply_to_address(address);
+
+                for (written, old) in mem::take(&mut memory) {
+                    written.subtract(addresses, &mut pieces);
+                    memory.extend(pieces.drain(..).map(|piece| (piece, old)));
+                }
+                memory.push((addresses, value));
+            }
+        }
+    }
+
+    Ok(memory
+        .iter()
+        .map(|&(addresses, value)| addresses.len() * value as u128)
+        .sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Every address of the set, one by one
+    fn expand(addresses: Addresses) -> Vec<u64> {
+        let mut all = vec![addresses.fixed];
+        for bit in (0..BITS)
+            .map(|i| 1 << i)
+            .filter(|b| addresses.floating & b != 0)
+        {
+            all.extend(all.clone().iter().map(|a| a | bit));
+        }
+        all
+    }
+
+    /// Part 2 writing every address separately
+    fn part2_naive(input: &str) -> u128 {
+        let mut mask = Mask::default();
+        let mut memory = HashMap::new();
+
+        for instruction in parse_program(input).unwrap() {
+            match instruction {
+                Instruction::Mask(m) => mask = m,
+                Instruction::Write { address, value } => {
+                    for a in expand(mask.apply_to_address(address)) {
+                        memory.insert(a, value as u128);
+                    }
+                }
+            }
+        }
+
+        memory.values().sum()
+    }
+
+    #[test]
+    fn examples() {
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\n\
+                     mem[8] = 11\n\
+                     mem[7] = 101\n\
+                     mem[8] = 0\n";
+        assert_eq!(part1(input).unwrap(), 165);
+
+        let input = "mask = 000000000000000000000000000000X1001X\n\
+                     mem[42] = 100\n\
+                     mask = 00000000000000000000000000000000X0XX\n\
+                     mem[26] = 1\n";
+        assert_eq!(part2(input).unwrap(), 208);
+    }
+
+    #[test]
+    fn write_before_mask() {
+        assert!(part1("mem[8] = 11\n").is_err());
+        assert!(part2("mem[8] = 11\n").is_err());
+    }
+
+    #[test]
+    fn subtract() {
+        let a = Addresses {
+            fixed: 0b0000,
+            floating: 0b1011,
+        };
+        let b = Addresses {
+            fixed: 0b0001,
+            floating: 0b0110,
+        };
+        let c = Addresses {
+            fixed: 0b0100,
+            floating: 0b0011,
+        };
+        assert!(a.intersects(b) && b.intersects(a));
+        assert!(!a.intersects(c) && !c.intersects(a));
+
+        let mut pieces = Vec::new();
+        a.subtract(b, &mut pieces);
+
+        let mut left = pieces.iter().flat_map(|&p| expand(p)).collect::<Vec<_>>();
+        left.sort_unstable();
+        let mut expected = expand(a)
+            .into_iter()
+            .filter(|x| !expand(b).contains(x))
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+
+        // Disjoint, so no address shows up twice
+        assert_eq!(left, expected);
+    }
+
+    #[test]
+    fn overlapping_floating_masks() {
+        let mut rng = StdRng::seed_from_u64(14);
+
+        for _ in 0..200 {
+            let mut input = String::new();
+
+            for _ in 0..rng.gen_range(1..8) {
+                // Only the low bits vary, so the writes overlap often
+                let mask = (0..BITS)
+                    .map(|i| match (i < BITS - 6, rng.gen_range(0..3)) {
+                        (true, _) | (false, 0) => '0',
+                        (false, 1) => '1',
+                        _ => 'X',
+                    })
+                    .collect::<String>();
+                input.push_str(&format!("mask = {}\n", mask));
+
+                for _ in 0..rng.gen_range(1..4) {
+                    let address = rng.gen_range(0..64);
+                    let value = rng.gen_range(0..1000);
+                    input.push_str(&format!("mem[{}] = {}\n", address, value));
+                }
+            }
+
+            assert_eq!(part2(&input).unwrap(), part2_naive(&input), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{bail, Context};
+
+/// Marks numbers that have not been spoken yet
+const UNSEEN: u32 = u32::MAX;
+
+fn parse_numbers(input: &str) -> anyhow::Result<Vec<u32>> {
+    input
+        .trim()
+        .split(',')
+        .map(|s| {
+            s.trim()
+                .parse()
+                .with_context(|| format!("Invalid starting number '{}'", s))
+        })
+        .collect()
+}
+
+/// The number spoken on turn `turn`, counting from one. Every number spoken is smaller than
+/// the number of turns, so the turn each was last spoken fits in a flat array.
+fn play(starting: &[u32], turn: usize) -> anyhow::Result<u32> {
+    if starting.is_empty() {
+        bail!("The game needs at least one starting number");
+    }
+    if turn == 0 {
+        bail!("Turns are counted from one");
+    }
+    if turn >= UNSEEN as usize {
+        bail!("Cannot play as far as turn {}", turn);
+    }
+    if turn <= starting.len() {
+        return Ok(starting[turn - 1]);
+    }
+
+    let largest = *starting.iter().max().unwrap() as usize;
+    let mut last_seen = vec![UNSEEN; turn.max(largest + 1)];
+
+    for (i, &n) in starting[..starting.len() - 1].iter().enumerate() {
+        last_seen[n as usize] = i as u32;
+    }
+
+    let mut spoken = starting[starting.len() - 1];
+
+    for i in starting.len() - 1..turn - 1 {
+        let seen = last_seen[spoken as usize];
+        last_seen[spoken as usize] = i as u32;
+        spoken = if seen == UNSEEN { 0 } else { i as u32 - seen };
+    }
+
+    Ok(spoken)
+}
+
+pub fn part1(input: &str, turn: Option<usize>) -> anyhow::Result<u32> {
+    play(&parse_numbers(input)?, turn.unwrap_or(2020))
+}
+
+pub fn part2(input: &str, turn: Option<usize>) -> anyhow::Result<u32> {
+    play(&parse_numbers(input)?, turn.unwrap_or(30_000_000))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples() {
+        assert_eq!(play(&[0, 3, 6], 10).unwrap(), 0);
+        assert_eq!(play(&[0, 3, 6], 2020).unwrap(), 436);
+        assert_eq!(play(&[1, 3, 2], 2020).unwrap(), 1);
+        assert_eq!(play(&[2, 1, 3], 2020).unwrap(), 10);
+        assert_eq!(play(&[3, 1, 2], 2020).unwrap(), 1836);
+        assert_eq!(play(&[0, 3, 6], 30_000_000).unwrap(), 175594);
+    }
+
+    #[test]
+    fn early_turns() {
+        // The starting numbers are spoken as they are
+        assert_eq!(play(&[7, 3, 9], 1).unwrap(), 7);
+        assert_eq!(play(&[7, 3, 9], 3).unwrap(), 9);
+        assert_eq!(play(&[7, 3, 9], 4).unwrap(), 0);
+        assert_eq!(part1("7,3,9", Some(2)).unwrap(), 3);
+
+        assert!(play(&[7, 3, 9], 0).is_err());
+        assert!(play(&[], 5).is_err());
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    use super::*;
+    use test::Bencher;
+
+    #[bench]
+    fn thirty_million_turns(b: &mut Bencher) {
+        b.iter(|| play(&[0, 3, 6], 30_000_000).unwrap());
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::bail;
+
+use crate::automaton::{Automaton, Backend, Offsets, Sparse};
+
+/// The active cubes of the initial slice, lifted into `N` dimensions
+fn parse_cubes<const N: usize>(input: &str) -> anyhow::Result<Sparse<bool, N>> {
+    let mut cubes = Sparse::new();
+
+    for (y, line) in input.lines().enumerate() {
+        for (x, c) in line.trim().chars().enumerate() {
+            match c {
+                '#' => {
+                    let mut p = [0; N];
+                    p[0] = x as i32;
+                    p[1] = y as i32;
+                    cubes.set(p, true);
+                }
+                '.' => {}
+                c => bail!("Invalid char on line {}: {:?}", y + 1, c),
+            }
+        }
+    }
+
+    Ok(cubes)
+}
+
+fn cube_rule(active: bool, neighbours: &[bool]) -> bool {
+    let n = neighbours.iter().filter(|&&x| x).count();
+    n == 3 || (active && n == 2)
+}
+
+/// Active cubes after the six cycles of the boot process
+fn boot<const N: usize>(input: &str) -> anyhow::Result<usize> {
+    let mut automaton = Automaton::new(parse_cubes::<N>(input)?, Offsets::moore(), cube_rule);
+    automaton.run(6);
+
+    Ok(automaton.grid().count(true))
+}
+
+pub fn part1(input: &str) -> anyhow::Result<usize> {
+    boot::<3>(input)
+}
+
+pub fn part2(input: &str) -> anyhow::Result<usize> {
+    boot::<4>(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = ".#.\n..#\n###\n";
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE).unwrap(), 112);
+        assert_eq!(part2(EXAMPLE).unwrap(), 848);
+    }
+}
@@ -0,0 +1,184 @@
+use thiserror::Error;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::lexer::Token;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Integer overflow")]
+    Overflow,
+    #[error("Negative exponent: {0}")]
+    NegativeExponent(i64),
+    #[error("Unbound variable: {0}")]
+    UnboundVariable(String),
+}
+
+pub type EvalResult = Result<i64, EvalError>;
+
+/// Values of the variables in an expression
+pub type Env = HashMap<String, i64>;
+
+pub trait Evaluable: std::fmt::Debug {
+    fn eval(&self, env: &Env) -> EvalResult;
+}
+
+pub(super) fn lookup(env: &Env, name: &str) -> EvalResult {
+    env.get(name)
+        .copied()
+        .ok_or_else(|| EvalError::UnboundVariable(name.to_owned()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl BinaryOp {
+    pub(super) fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Add => Some(BinaryOp::Add),
+            Token::Sub => Some(BinaryOp::Sub),
+            Token::Mul => Some(BinaryOp::Mul),
+            Token::Div => Some(BinaryOp::Div),
+            Token::Rem => Some(BinaryOp::Rem),
+            Token::Pow => Some(BinaryOp::Pow),
+            _ => None,
+        }
+    }
+
+    /// Applies the operator, returning an error instead of overflowing or dividing by zero.
+    /// Division and remainder truncate towards zero.
+    pub(super) fn apply(self, lhs: i64, rhs: i64) -> EvalResult {
+        let result = match self {
+            BinaryOp::Add => lhs.checked_add(rhs),
+            BinaryOp::Sub => lhs.checked_sub(rhs),
+            BinaryOp::Mul => lhs.checked_mul(rhs),
+            BinaryOp::Div | BinaryOp::Rem if rhs == 0 => return Err(EvalError::DivisionByZero),
+            BinaryOp::Div => lhs.checked_div(rhs),
+            BinaryOp::Rem => lhs.checked_rem(rhs),
+            BinaryOp::Pow if rhs < 0 => return Err(EvalError::NegativeExponent(rhs)),
+            BinaryOp::Pow => u32::try_from(rhs).ok().and_then(|exp| lhs.checked_pow(exp)),
+        };
+
+        result.ok_or(EvalError::Overflow)
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Pow => "^",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+pub(super) fn negate(value: i64) -> EvalResult {
+    value.checked_neg().ok_or(EvalError::Overflow)
+}
+
+/// Index of a node within its [`Ast`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(u32);
+
+/// Index of a variable name within its [`Ast`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VarId(pub(super) u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Node {
+    Literal(i64),
+    Var(VarId),
+    Negate(NodeId),
+    Binary(BinaryOp, NodeId, NodeId),
+}
+
+/// An expression tree whose nodes live in a single `Vec` and refer to each other by index,
+/// rather than being allocated one by one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ast {
+    nodes: Vec<Node>,
+    names: Vec<String>,
+}
+
+impl Ast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() as u32 - 1)
+    }
+
+    pub fn node(&self, id: NodeId) -> Node {
+        self.nodes[id.0 as usize]
+    }
+
+    /// Returns the id of a variable name, adding it if it is new
+    pub fn intern(&mut self, name: &str) -> VarId {
+        let index = match self.names.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name.to_owned());
+                self.names.len() - 1
+            }
+        };
+
+        VarId(index as u32)
+    }
+
+    pub fn name(&self, id: VarId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Shorthand for pushing a variable node
+    pub fn var(&mut self, name: &str) -> NodeId {
+        let id = self.intern(name);
+        self.push(Node::Var(id))
+    }
+
+    /// Children are always pushed before their parent, so the root is the last node
+    pub fn root(&self) -> NodeId {
+        assert!(!self.nodes.is_empty(), "Empty expression tree");
+        NodeId(self.nodes.len() as u32 - 1)
+    }
+
+    fn eval_node(&self, id: NodeId, env: &Env) -> EvalResult {
+        match self.node(id) {
+            Node::Literal(n) => Ok(n),
+            Node::Var(var) => lookup(env, self.name(var)),
+            Node::Negate(expr) => negate(self.eval_node(expr, env)?),
+            Node::Binary(op, lhs, rhs) => {
+                op.apply(self.eval_node(lhs, env)?, self.eval_node(rhs, env)?)
+            }
+        }
+    }
+}
+
+/// Evaluates by walking the tree from the root
+impl Evaluable for Ast {
+    fn eval(&self, env: &Env) -> EvalResult {
+        self.eval_node(self.root(), env)
+    }
+}
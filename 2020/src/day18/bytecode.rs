@@ -0,0 +1,83 @@
+use super::ast::{lookup, negate, Ast, BinaryOp, Env, EvalResult, Evaluable, Node, NodeId};
+
+/// A single stack machine instruction, eight bytes in size. Operands are popped from the
+/// stack, with the right hand side on top, and the result is pushed back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Pushes the constant at the given index
+    Const(u32),
+    /// Pushes the value of the variable with the given name index
+    Load(u32),
+    Neg,
+    Binary(BinaryOp),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    ops: Vec<Op>,
+    constants: Vec<i64>,
+    names: Vec<String>,
+    /// The deepest the stack gets while running, so it is allocated only once
+    max_depth: usize,
+}
+
+impl Program {
+    pub fn compile(ast: &Ast) -> Self {
+        let mut program = Self {
+            names: ast.names().to_vec(),
+            ..Self::default()
+        };
+        program.max_depth = program.emit(ast, ast.root());
+        program
+    }
+
+    /// Emits the instructions in post-order and returns the stack depth they need
+    fn emit(&mut self, ast: &Ast, id: NodeId) -> usize {
+        match ast.node(id) {
+            Node::Literal(n) => {
+                self.ops.push(Op::Const(self.constants.len() as u32));
+                self.constants.push(n);
+                1
+            }
+            Node::Var(var) => {
+                self.ops.push(Op::Load(var.0));
+                1
+            }
+            Node::Negate(expr) => {
+                let depth = self.emit(ast, expr);
+                self.ops.push(Op::Neg);
+                depth
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let lhs_depth = self.emit(ast, lhs);
+                let rhs_depth = self.emit(ast, rhs);
+                self.ops.push(Op::Binary(op));
+                lhs_depth.max(rhs_depth + 1)
+            }
+        }
+    }
+}
+
+impl Evaluable for Program {
+    fn eval(&self, env: &Env) -> EvalResult {
+        let mut stack = Vec::with_capacity(self.max_depth);
+
+        for &op in &self.ops {
+            match op {
+                Op::Const(i) => stack.push(self.constants[i as usize]),
+                Op::Load(i) => stack.push(lookup(env, &self.names[i as usize])?),
+                Op::Neg => {
+                    let value = stack.pop().expect("Malformed program");
+                    stack.push(negate(value)?);
+                }
+                Op::Binary(op) => {
+                    let rhs = stack.pop().expect("Malformed program");
+                    let lhs = stack.pop().expect("Malformed program");
+                    stack.push(op.apply(lhs, rhs)?);
+                }
+            }
+        }
+
+        Ok(stack.pop().expect("Malformed program"))
+    }
+}
@@ -0,0 +1,431 @@
+//! Differential testing of the Pratt parsers against a shunting-yard reference evaluator,
+//! on randomly generated expressions.
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use std::convert::TryFrom;
+
+use super::ast::{Env, Evaluable};
+use super::bytecode::Program;
+use super::lexer::{ParseErrorKind, Tokens};
+use super::{parser1, parser2, ParseResult};
+
+/// What the reference evaluator expects from an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Value(i64),
+    /// The expression is malformed
+    Syntax,
+    /// The expression is well formed, but evaluating it fails
+    Eval,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Ident,
+    Op(char),
+    Neg,
+    Open,
+    Close,
+}
+
+/// Precedence and associativity of a binary operator, `true` meaning right-associative
+type Rules = fn(char) -> (u8, bool);
+
+fn rules1(_: char) -> (u8, bool) {
+    (1, false)
+}
+
+fn rules2(op: char) -> (u8, bool) {
+    match op {
+        '^' => (3, true),
+        '+' | '-' => (2, false),
+        _ => (1, false),
+    }
+}
+
+/// Negation only applies to the term that directly follows it
+const NEG_PRECEDENCE: u8 = u8::MAX;
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let chars = s.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        i += 1;
+
+        let token = match c {
+            c if c.is_ascii_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+            '0'..='9' => {
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits = chars[start..i].iter().collect::<String>();
+                Token::Num(digits.parse().ok()?)
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Ident
+            }
+            _ => return None,
+        };
+
+        tokens.push(token);
+    }
+
+    Some(tokens)
+}
+
+/// Converts infix tokens to reverse Polish notation, or `None` if they are malformed
+fn to_rpn(tokens: Vec<Token>, rules: Rules) -> Option<Vec<Token>> {
+    let precedence = |token: &Token| match token {
+        Token::Op(op) => Some(rules(*op)),
+        Token::Neg => Some((NEG_PRECEDENCE, true)),
+        _ => None,
+    };
+
+    let mut output = Vec::new();
+    let mut stack = Vec::<Token>::new();
+    // Whether the next token has to start an operand
+    let mut expect_operand = true;
+
+    for token in tokens {
+        match (token, expect_operand) {
+            (token @ Token::Num(_), true) | (token @ Token::Ident, true) => {
+                output.push(token);
+                expect_operand = false;
+            }
+            (Token::Op('-'), true) => stack.push(Token::Neg),
+            (Token::Open, true) => stack.push(Token::Open),
+            (Token::Op(op), false) => {
+                let (prec, right) = rules(op);
+
+                while let Some((top, _)) = stack.last().and_then(precedence) {
+                    if top > prec || (top == prec && !right) {
+                        output.push(stack.pop()?);
+                    } else {
+                        break;
+                    }
+                }
+
+                stack.push(Token::Op(op));
+                expect_operand = true;
+            }
+            (Token::Close, false) => loop {
+                match stack.pop()? {
+                    Token::Open => break,
+                    token => output.push(token),
+                }
+            },
+            _ => return None,
+        }
+    }
+
+    if expect_operand {
+        return None;
+    }
+
+    while let Some(token) = stack.pop() {
+        if token == Token::Open {
+            return None;
+        }
+        output.push(token);
+    }
+
+    Some(output)
+}
+
+fn apply(op: char, lhs: i64, rhs: i64) -> Option<i64> {
+    match op {
+        '+' => lhs.checked_add(rhs),
+        '-' => lhs.checked_sub(rhs),
+        '*' => lhs.checked_mul(rhs),
+        '/' => lhs.checked_div(rhs),
+        '%' => lhs.checked_rem(rhs),
+        '^' => lhs.checked_pow(u32::try_from(rhs).ok()?),
+        _ => unreachable!("Unknown operator {}", op),
+    }
+}
+
+/// Evaluates reverse Polish notation, where every variable is unbound
+fn eval_rpn(rpn: &[Token]) -> Option<i64> {
+    let mut stack = Vec::new();
+
+    for token in rpn {
+        match *token {
+            Token::Num(n) => stack.push(n),
+            Token::Ident => return None,
+            Token::Neg => {
+                let value = stack.pop()?;
+                stack.push(value.checked_neg()?);
+            }
+            Token::Op(op) => {
+                let rhs = stack.pop()?;
+                let lhs = stack.pop()?;
+                stack.push(apply(op, lhs, rhs)?);
+            }
+            Token::Open | Token::Close => unreachable!("Parentheses in RPN"),
+        }
+    }
+
+    stack.pop()
+}
+
+fn reference(s: &str, rules: Rules) -> Outcome {
+    match tokenize(s).and_then(|tokens| to_rpn(tokens, rules)) {
+        None => Outcome::Syntax,
+        Some(rpn) => eval_rpn(&rpn).map_or(Outcome::Eval, Outcome::Value),
+    }
+}
+
+/// How deeply generated expressions nest, comfortably below the limit of the parser
+const MAX_NESTING: usize = 200;
+
+/// Builds random, well formed expressions as strings
+struct Generator {
+    rng: StdRng,
+    out: String,
+    /// Parentheses and negations enclosing the current term
+    nesting: usize,
+}
+
+impl Generator {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            out: String::new(),
+            nesting: 0,
+        }
+    }
+
+    fn expr(&mut self) -> String {
+        self.out.clear();
+        let depth = self.rng.gen_range(0..6);
+        self.chain(depth);
+        self.out.clone()
+    }
+
+    fn space(&mut self) {
+        match self.rng.gen_range(0..6) {
+            0 | 1 => {}
+            2 => self.out.push_str("  "),
+            3 => self.out.push('\t'),
+            _ => self.out.push(' '),
+        }
+    }
+
+    /// A chain of terms separated by operators, occasionally a very long one
+    fn chain(&mut self, depth: u32) {
+        let len = if self.rng.gen_ratio(1, 20) {
+            self.rng.gen_range(20..60)
+        } else {
+            self.rng.gen_range(1..5)
+        };
+
+        for i in 0..len {
+            if i > 0 {
+                self.space();
+                let op = *['+', '+', '+', '*', '*', '*', '-', '-', '/', '%', '^']
+                    .choose(&mut self.rng)
+                    .unwrap();
+                self.out.push(op);
+                self.space();
+            }
+            self.term(depth);
+        }
+    }
+
+    fn term(&mut self, depth: u32) {
+        let room = MAX_NESTING - self.nesting;
+
+        match self.rng.gen_range(0..10) {
+            0 if room > 0 => {
+                self.out.push('-');
+                self.space();
+                self.nesting += 1;
+                self.term(depth);
+                self.nesting -= 1;
+            }
+            // Deeply nested parentheses around a single term
+            1 if room > 10 && self.rng.gen_ratio(1, 10) => {
+                let nesting = self.rng.gen_range(10..room.min(150));
+                (0..nesting).for_each(|_| self.out.push('('));
+                self.nesting += nesting;
+                self.term(depth);
+                self.nesting -= nesting;
+                (0..nesting).for_each(|_| self.out.push(')'));
+            }
+            2..=4 if depth > 0 && room > 0 => {
+                self.out.push('(');
+                self.space();
+                self.nesting += 1;
+                self.chain(depth - 1);
+                self.nesting -= 1;
+                self.space();
+                self.out.push(')');
+            }
+            _ => {
+                let n = match self.rng.gen_range(0..20) {
+                    0 => self.rng.gen_range(0..=i64::MAX),
+                    1 => 0,
+                    _ => self.rng.gen_range(1..10),
+                };
+                self.out.push_str(&n.to_string());
+            }
+        }
+    }
+
+    /// Breaks an expression by inserting, deleting or duplicating characters, or cutting it
+    /// short. The result is often, but not always, malformed.
+    fn mutate(&mut self, s: &str) -> String {
+        const NOISE: &[char] = &[
+            '(', ')', '+', '-', '*', '/', '%', '^', '#', '.', '=', 'x', '9', ' ',
+        ];
+
+        let mut chars = s.chars().collect::<Vec<_>>();
+
+        for _ in 0..self.rng.gen_range(1..4) {
+            let i = self.rng.gen_range(0..=chars.len());
+
+            match self.rng.gen_range(0..4) {
+                0 => chars.insert(i, *NOISE.choose(&mut self.rng).unwrap()),
+                1 if i < chars.len() => {
+                    chars.remove(i);
+                }
+                2 if i < chars.len() => chars.insert(i, chars[i]),
+                _ => chars.truncate(i),
+            }
+        }
+
+        chars.into_iter().collect()
+    }
+}
+
+/// Compares the outcome of parsing and evaluating against the reference
+fn check(s: &str, parse: fn(&mut Tokens) -> ParseResult, rules: Rules) -> Outcome {
+    let expected = reference(s, rules);
+
+    let actual = match parse(&mut Tokens::new(s)) {
+        Err(_) => Outcome::Syntax,
+        Ok(ast) => {
+            let value = ast.eval(&Env::new());
+            assert_eq!(
+                value,
+                Program::compile(&ast).eval(&Env::new()),
+                "Tree walk and bytecode disagree on {:?}",
+                s
+            );
+            value.map_or(Outcome::Eval, Outcome::Value)
+        }
+    };
+
+    assert_eq!(actual, expected, "Mismatch on {:?}", s);
+    actual
+}
+
+const CASES: u64 = 5_000;
+
+#[test]
+fn reference_examples() {
+    assert_eq!(reference("2 + 3 * 4", rules1), Outcome::Value(20));
+    assert_eq!(reference("2 + 3 * 4", rules2), Outcome::Value(20));
+    assert_eq!(reference("2 * 3 + 4", rules2), Outcome::Value(14));
+    assert_eq!(reference("2 ^ 3 ^ 2", rules2), Outcome::Value(512));
+    assert_eq!(reference("2 ^ 3 ^ 2", rules1), Outcome::Value(64));
+    assert_eq!(reference("-2 ^ 2", rules2), Outcome::Value(4));
+    assert_eq!(reference("((2))(3)", rules2), Outcome::Syntax);
+    assert_eq!(reference("1 / 0", rules2), Outcome::Eval);
+    assert_eq!(reference("x", rules2), Outcome::Eval);
+}
+
+#[test]
+fn too_deep() {
+    for expr in &[
+        "(".repeat(100_000),
+        "-".repeat(100_000),
+        "2 ^ ".repeat(100_000),
+    ] {
+        let err = parser2::parse_expr(&mut Tokens::new(expr)).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::TooDeep);
+    }
+
+    let expr = format!("{}1{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING));
+    assert_eq!(
+        parser1::parse_expr(&mut Tokens::new(&expr))
+            .unwrap()
+            .eval(&Env::new()),
+        Ok(1)
+    );
+}
+
+#[test]
+fn long_chain() {
+    // Flat, so only the height of the tree limits how long it can get
+    let chain = |len: usize| vec!["1"; len].join(" + ");
+
+    for parse in &[parser1::parse_expr, parser2::parse_expr] {
+        let err = parse(&mut Tokens::new(&chain(30_000))).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::TooDeep);
+    }
+
+    assert_eq!(
+        check(&chain(1000), parser1::parse_expr, rules1),
+        Outcome::Value(1000)
+    );
+    assert_eq!(
+        check(&chain(1000), parser2::parse_expr, rules2),
+        Outcome::Value(1000)
+    );
+}
+
+#[test]
+fn differential() {
+    let mut generator = Generator::new(18);
+    let mut values = 0;
+
+    for _ in 0..CASES {
+        let expr = generator.expr();
+        check(&expr, parser1::parse_expr, rules1);
+
+        if let Outcome::Value(_) = check(&expr, parser2::parse_expr, rules2) {
+            values += 1;
+        }
+    }
+
+    // Most expressions should evaluate, rather than all overflowing
+    assert!(values > CASES / 2, "Only {} expressions evaluated", values);
+}
+
+#[test]
+fn malformed() {
+    let mut generator = Generator::new(2020);
+    let mut rejected = 0;
+
+    for _ in 0..CASES {
+        let expr = generator.expr();
+        let broken = generator.mutate(&expr);
+
+        if reference(&broken, rules2) == Outcome::Syntax {
+            rejected += 1;
+        }
+
+        check(&broken, parser1::parse_expr, rules1);
+        check(&broken, parser2::parse_expr, rules2);
+    }
+
+    // Make sure the mutations actually exercise the error paths
+    assert!(
+        rejected > CASES / 2,
+        "Only {} malformed expressions",
+        rejected
+    );
+}
@@ -0,0 +1,212 @@
+use thiserror::Error;
+
+use std::str;
+
+use super::ast::BinaryOp;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    #[error("Unexpected character '{0}'")]
+    UnexpectedChar(char),
+    #[error("Number does not fit in 64 bits")]
+    NumberTooLarge,
+    #[error("Unexpected token: {0:?}")]
+    UnexpectedToken(Token),
+    #[error("Unexpected end of input")]
+    UnexpectedEnd,
+    #[error("Operator {0:?} is not in the operator table")]
+    UnsupportedOperator(BinaryOp),
+    #[error("Unclosed parenthesis")]
+    UnclosedParen,
+    #[error("Unmatched closing parenthesis")]
+    UnmatchedParen,
+    #[error("Unexpected input after expression")]
+    TrailingInput,
+    #[error("Expression is nested too deeply or too long")]
+    TooDeep,
+}
+
+/// A parse error, displayed with the offending line and a caret under the problem
+///
+/// ```text
+/// Unclosed parenthesis
+/// 2 * (3 + 4
+///     ^
+/// ```
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{kind}\n{line}\n{caret}")]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+    line: String,
+    caret: String,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, span: Span, source: &[u8]) -> Self {
+        let line = String::from_utf8_lossy(source).into_owned();
+
+        // Columns are counted in characters, as the span is in bytes
+        let chars = |bytes: &[u8]| String::from_utf8_lossy(bytes).chars().count();
+        let start = span.start.min(source.len());
+        let end = span.end.min(source.len());
+
+        let column = chars(&source[..start]);
+        let width = chars(&source[start..end]).max(1);
+
+        let caret = format!("{}{}", " ".repeat(column), "^".repeat(width));
+
+        Self {
+            kind,
+            span,
+            line,
+            caret,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Num(i64),
+    Ident(String),
+    Mul,
+    Add,
+    Sub,
+    Div,
+    Rem,
+    Pow,
+    ParenOpen,
+    ParenClose,
+}
+
+/// Byte range of a token within its line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub(super) fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+pub type SpannedToken = (Token, Span);
+
+#[derive(Default, Clone, Copy)]
+pub struct Tokens<'a> {
+    source: &'a [u8],
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(super) fn new(s: &'a str) -> Self {
+        Self {
+            source: s.as_bytes(),
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn peek(&mut self) -> Result<Option<SpannedToken>, ParseError> {
+        let prev_start = self.start;
+        let prev_end = self.end;
+
+        let result = self.next().transpose();
+
+        self.start = prev_start;
+        self.end = prev_end;
+
+        result
+    }
+
+    pub(super) fn advance(&mut self) -> Result<Option<SpannedToken>, ParseError> {
+        self.next().transpose()
+    }
+
+    pub(super) fn error(&self, kind: ParseErrorKind, span: Span) -> ParseError {
+        ParseError::new(kind, span, self.source)
+    }
+
+    /// Span just past the last token, used to point at a missing token
+    pub(super) fn end_span(&self) -> Span {
+        let end = self.source.len();
+        Span::new(end, end + 1)
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<SpannedToken, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Consume leading whitespace
+        while self.end < self.source.len() && self.source[self.end].is_ascii_whitespace() {
+            self.end += 1;
+        }
+
+        // Return if cursor is at the end of source
+        if self.end == self.source.len() {
+            return None;
+        }
+
+        // Align start and end cursor
+        self.start = self.end;
+
+        // Match token
+        self.end += 1;
+        let token = match self.source[self.start] {
+            b'+' => Token::Add,
+            b'-' => Token::Sub,
+            b'*' => Token::Mul,
+            b'/' => Token::Div,
+            b'%' => Token::Rem,
+            b'^' => Token::Pow,
+            b'(' => Token::ParenOpen,
+            b')' => Token::ParenClose,
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                // Move cursor to the end of the identifier
+                while self.end < self.source.len()
+                    && matches!(self.source[self.end], b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_')
+                {
+                    self.end += 1;
+                }
+
+                let name = str::from_utf8(&self.source[self.start..self.end]).unwrap();
+
+                Token::Ident(name.to_owned())
+            }
+            b'0'..=b'9' => {
+                // Move cursor to the last digit
+                while self.end < self.source.len() && self.source[self.end].is_ascii_digit() {
+                    self.end += 1;
+                }
+
+                let n = str::from_utf8(&self.source[self.start..self.end])
+                    .unwrap()
+                    .parse::<i64>();
+
+                match n {
+                    Ok(n) => Token::Num(n),
+                    Err(_) => {
+                        let span = Span::new(self.start, self.end);
+                        return Some(Err(self.error(ParseErrorKind::NumberTooLarge, span)));
+                    }
+                }
+            }
+            _ => {
+                // Consume the whole character, which may be more than a single byte
+                let c = String::from_utf8_lossy(&self.source[self.start..])
+                    .chars()
+                    .next()
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                self.end = (self.start + c.len_utf8()).min(self.source.len());
+
+                let span = Span::new(self.start, self.end);
+                return Some(Err(self.error(ParseErrorKind::UnexpectedChar(c), span)));
+            }
+        };
+
+        Some(Ok((token, Span::new(self.start, self.end))))
+    }
+}
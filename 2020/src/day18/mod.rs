@@ -0,0 +1,530 @@
+use anyhow::Context;
+
+mod ast;
+mod bytecode;
+#[cfg(test)]
+mod fuzz;
+mod lexer;
+mod parser;
+mod pretty;
+mod repl;
+mod symbolic;
+
+use ast::{BinaryOp, Env, EvalError, Evaluable};
+use bytecode::Program;
+use lexer::Tokens;
+use parser::{parse_expr, Assoc, OperatorTable, ParseResult};
+
+pub use repl::repl;
+
+pub mod parser1 {
+    use super::*;
+
+    /// For the first part of this exercise, the order of operations does not matter
+    pub fn table() -> OperatorTable {
+        OperatorTable::new()
+            .with(BinaryOp::Add, 1, Assoc::Left)
+            .with(BinaryOp::Sub, 1, Assoc::Left)
+            .with(BinaryOp::Mul, 1, Assoc::Left)
+            .with(BinaryOp::Div, 1, Assoc::Left)
+            .with(BinaryOp::Rem, 1, Assoc::Left)
+            .with(BinaryOp::Pow, 1, Assoc::Left)
+    }
+
+    /// We can parse expressions such as:
+    ///
+    /// ```
+    /// assert_eq!(parse_expr("1"), 1);
+    /// assert_eq!(parse_expr("2 + 3"), 5);
+    /// assert_eq!(parse_expr("(5 + 3) + 4"), 12);
+    /// assert_eq!(parse_expr("4 * (3 + 2)"), 20);
+    /// ```
+    ///
+    /// Note how operator precedence does not matter:
+    /// ```
+    /// assert_eq!(parse_expr("2 + 3 * 4"), 20);
+    /// ```
+    pub fn parse_expr(tokens: &mut Tokens) -> ParseResult {
+        super::parse_expr(tokens, &table())
+    }
+}
+
+pub mod parser2 {
+    use super::*;
+
+    /// For the second part of this exercise, addition has a higher precedence than multiplication.
+    /// Subtraction ranks with addition, division and remainder with multiplication, and
+    /// exponentiation binds tightest of all and is right-associative.
+    pub fn table() -> OperatorTable {
+        OperatorTable::new()
+            .with(BinaryOp::Pow, 3, Assoc::Right)
+            .with(BinaryOp::Add, 2, Assoc::Left)
+            .with(BinaryOp::Sub, 2, Assoc::Left)
+            .with(BinaryOp::Mul, 1, Assoc::Left)
+            .with(BinaryOp::Div, 1, Assoc::Left)
+            .with(BinaryOp::Rem, 1, Assoc::Left)
+    }
+
+    /// We can parse expressions such as:
+    ///
+    /// ```
+    /// assert_eq!("1 + (2 * 3) + (4 * (5 + 6))", 51);
+    /// assert_eq!("2 * 3 + (4 * 5)", 46);
+    /// assert_eq!("5 + (8 * 3 + 9 + 3 * 4 * 3)", 1445);
+    /// assert_eq!("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 669060);
+    /// assert_eq!("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 23340);
+    /// ```
+    ///
+    /// Note how addition his higher precedence than multiplication
+    /// ```
+    /// assert_eq!(parse_expr("3 * 4 + 2"), 18);
+    /// ```
+    pub fn parse_expr(tokens: &mut Tokens) -> ParseResult {
+        super::parse_expr(tokens, &table())
+    }
+}
+
+fn sum_exprs(input: &str, parse_expr: fn(&mut Tokens) -> ParseResult) -> anyhow::Result<i64> {
+    input.lines().enumerate().try_fold(0i64, |sum, (i, expr)| {
+        let value = parse_expr(&mut Tokens::new(expr))
+            .map_err(anyhow::Error::from)
+            .and_then(|ast| Ok(Program::compile(&ast).eval(&Env::new())?))
+            .with_context(|| format!("Failed to evaluate line {}", i + 1))?;
+
+        Ok(sum.checked_add(value).ok_or(EvalError::Overflow)?)
+    })
+}
+
+pub fn part1(input: &str) -> anyhow::Result<i64> {
+    sum_exprs(input, parser1::parse_expr)
+}
+
+pub fn part2(input: &str) -> anyhow::Result<i64> {
+    sum_exprs(input, parser2::parse_expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ast::EvalResult;
+    use super::lexer::{ParseError, ParseErrorKind, Span, Token};
+    use super::*;
+
+    fn try_eval(table: &OperatorTable, expr: &str) -> EvalResult {
+        let ast = parse_expr(&mut Tokens::new(expr), table).unwrap();
+        let value = ast.eval(&Env::new());
+
+        assert_eq!(Program::compile(&ast).eval(&Env::new()), value);
+
+        value
+    }
+
+    fn eval(table: &OperatorTable, expr: &str) -> i64 {
+        try_eval(table, expr).unwrap()
+    }
+
+    #[test]
+    fn no_precedence() {
+        let table = parser1::table();
+
+        assert_eq!(eval(&table, "1"), 1);
+        assert_eq!(eval(&table, "2 + 3"), 5);
+        assert_eq!(eval(&table, "(5 + 3) + 4"), 12);
+        assert_eq!(eval(&table, "4 * (3 + 2)"), 20);
+        assert_eq!(eval(&table, "2 + 3 * 4"), 20);
+        assert_eq!(eval(&table, "1 + 2 * 3 + 4 * 5 + 6"), 71);
+        assert_eq!(eval(&table, "1 + (2 * 3) + (4 * (5 + 6))"), 51);
+        assert_eq!(eval(&table, "2 * 3 + (4 * 5)"), 26);
+        assert_eq!(eval(&table, "5 + (8 * 3 + 9 + 3 * 4 * 3)"), 437);
+        assert_eq!(
+            eval(&table, "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"),
+            12240
+        );
+        assert_eq!(
+            eval(&table, "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"),
+            13632
+        );
+    }
+
+    #[test]
+    fn addition_first() {
+        let table = parser2::table();
+
+        assert_eq!(eval(&table, "1 + (2 * 3) + (4 * (5 + 6))"), 51);
+        assert_eq!(eval(&table, "2 * 3 + (4 * 5)"), 46);
+        assert_eq!(eval(&table, "5 + (8 * 3 + 9 + 3 * 4 * 3)"), 1445);
+        assert_eq!(
+            eval(&table, "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"),
+            669060
+        );
+        assert_eq!(
+            eval(&table, "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"),
+            23340
+        );
+        assert_eq!(eval(&table, "3 * 4 + 2"), 18);
+    }
+
+    #[test]
+    fn multiplication_first() {
+        let table = OperatorTable::new()
+            .with(BinaryOp::Add, 1, Assoc::Left)
+            .with(BinaryOp::Mul, 2, Assoc::Left);
+
+        assert_eq!(eval(&table, "2 + 3 * 4"), 14);
+        assert_eq!(eval(&table, "3 * 4 + 2"), 14);
+    }
+
+    #[test]
+    fn extended_operators() {
+        let table = parser1::table();
+
+        assert_eq!(eval(&table, "10 - 4 - 3"), 3);
+        assert_eq!(eval(&table, "2 + 10 / 4"), 3);
+        assert_eq!(eval(&table, "17 % 5 * 2"), 4);
+        assert_eq!(eval(&table, "1 + 2 ^ 3"), 27);
+        assert_eq!(eval(&table, "-7 / 2"), -3);
+        assert_eq!(eval(&table, "3 * -(2 + 1)"), -9);
+
+        let table = parser2::table();
+
+        assert_eq!(eval(&table, "10 - 4 - 3"), 3);
+        assert_eq!(eval(&table, "2 * 3 - 1"), 4);
+        assert_eq!(eval(&table, "20 / 2 + 3"), 4);
+        assert_eq!(eval(&table, "2 ^ 3 ^ 2"), 512);
+        assert_eq!(eval(&table, "2 * 2 ^ 3 + 1"), 18);
+        assert_eq!(eval(&table, "-2 ^ 2"), 4);
+        assert_eq!(eval(&table, "--5"), 5);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let table = parser2::table();
+
+        assert_eq!(try_eval(&table, "1 / 0"), Err(EvalError::DivisionByZero));
+        assert_eq!(
+            try_eval(&table, "1 % (2 - 2)"),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(try_eval(&table, "2 ^ 63"), Err(EvalError::Overflow));
+        assert_eq!(
+            try_eval(&table, "2 ^ -1"),
+            Err(EvalError::NegativeExponent(-1))
+        );
+        assert_eq!(
+            try_eval(&table, "9223372036854775807 + 1"),
+            Err(EvalError::Overflow)
+        );
+        assert_eq!(
+            try_eval(&table, "(-9223372036854775807 - 1) / -1"),
+            Err(EvalError::Overflow)
+        );
+        assert_eq!(eval(&table, "-9223372036854775807 - 1"), i64::MIN);
+    }
+
+    fn parse_error(expr: &str) -> ParseError {
+        parse_expr(&mut Tokens::new(expr), &parser2::table()).unwrap_err()
+    }
+
+    #[test]
+    fn missing_operator() {
+        let table = OperatorTable::new().with(BinaryOp::Add, 1, Assoc::Left);
+        let err = parse_expr(&mut Tokens::new("2 * 3"), &table).unwrap_err();
+
+        assert_eq!(err.kind, ParseErrorKind::UnsupportedOperator(BinaryOp::Mul));
+        assert_eq!(err.span, Span::new(2, 3));
+    }
+
+    #[test]
+    fn pretty_print() {
+        use super::pretty::{Parenthesized, SExpr};
+
+        let ast = parser2::parse_expr(&mut Tokens::new("2 * 3 + (4 * 5)")).unwrap();
+        assert_eq!(Parenthesized(&ast).to_string(), "(2 * (3 + (4 * 5)))");
+        assert_eq!(SExpr(&ast).to_string(), "(* 2 (+ 3 (* 4 5)))");
+
+        let ast = parser1::parse_expr(&mut Tokens::new("-1 - 2 ^ 3")).unwrap();
+        assert_eq!(Parenthesized(&ast).to_string(), "(((-1) - 2) ^ 3)");
+        assert_eq!(SExpr(&ast).to_string(), "(^ (- (- 1) 2) 3)");
+    }
+
+    #[test]
+    fn repl_session() {
+        let input = "2 * 3 + (4 * 5)\n:mode 2\n:sexpr\n1 + 2 * 3\n1 +\n";
+        let mut output = Vec::new();
+
+        repl(input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[1], "> part 1: ((2 * 3) + (4 * 5)) = 26");
+        assert_eq!(lines[2], "part 2: (2 * (3 + (4 * 5))) = 46");
+        assert_eq!(lines[3], "> > > part 2: (* (+ 1 2) 3) = 9");
+        assert_eq!(lines[4], "> part 2: Unexpected end of input");
+    }
+
+    #[test]
+    fn variables() {
+        let ast = parser2::parse_expr(&mut Tokens::new("x * (y + 3)")).unwrap();
+        let env = [("x".to_owned(), 2), ("y".to_owned(), 4)]
+            .iter()
+            .cloned()
+            .collect::<Env>();
+
+        assert_eq!(ast.eval(&env), Ok(14));
+        assert_eq!(Program::compile(&ast).eval(&env), Ok(14));
+        assert_eq!(
+            ast.eval(&Env::new()),
+            Err(EvalError::UnboundVariable("x".to_owned()))
+        );
+    }
+
+    #[test]
+    fn constant_folding() {
+        use super::pretty::Parenthesized;
+
+        let ast = parser2::parse_expr(&mut Tokens::new("x * (2 + 3) + y * 2")).unwrap();
+        let env = [("y".to_owned(), 4)].iter().cloned().collect::<Env>();
+
+        let folded = ast.fold(&env).unwrap();
+        assert_eq!(Parenthesized(&folded).to_string(), "((x * 9) * 2)");
+
+        let ast = parser1::parse_expr(&mut Tokens::new("x * (2 + 3) + y * 2")).unwrap();
+        let folded = ast.fold(&env).unwrap();
+        assert_eq!(Parenthesized(&folded).to_string(), "(((x * 5) + 4) * 2)");
+    }
+
+    #[test]
+    fn solve_linear() {
+        use super::symbolic::{solve, SolveError};
+
+        let parse = |expr| parser2::parse_expr(&mut Tokens::new(expr)).unwrap();
+        let env = [("pppw".to_owned(), 2)].iter().cloned().collect::<Env>();
+
+        let lhs = parse("(4 + (2 * (humn - 3))) / pppw");
+        let rhs = parse("150");
+        assert_eq!(
+            solve(&lhs, &rhs, "humn", &Env::new()),
+            Err(SolveError::Eval(EvalError::UnboundVariable(
+                "pppw".to_owned()
+            )))
+        );
+        assert_eq!(solve(&lhs, &rhs, "humn", &env), Ok(151));
+
+        assert_eq!(solve(&parse("-x"), &parse("3 - (2 * x)"), "x", &env), Ok(3));
+        assert_eq!(
+            solve(&parse("x * x"), &parse("4"), "x", &env),
+            Err(SolveError::NonLinear("x".to_owned()))
+        );
+        assert_eq!(
+            solve(&parse("x - x"), &parse("4"), "x", &env),
+            Err(SolveError::NoUniqueSolution("x".to_owned()))
+        );
+        assert_eq!(
+            solve(&parse("2 * x"), &parse("5"), "x", &env),
+            Err(SolveError::NoIntegerSolution("x".to_owned()))
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let tokens = Tokens::new(" 12 + (3)")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Num(12), Span::new(1, 3)),
+                (Token::Add, Span::new(4, 5)),
+                (Token::ParenOpen, Span::new(6, 7)),
+                (Token::Num(3), Span::new(7, 8)),
+                (Token::ParenClose, Span::new(8, 9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn highest_precedence() {
+        let table = OperatorTable::new()
+            .with(BinaryOp::Add, 1, Assoc::Left)
+            .with(BinaryOp::Mul, u8::MAX, Assoc::Left)
+            .with(BinaryOp::Pow, u8::MAX, Assoc::Right);
+
+        assert_eq!(eval(&table, "1 + 2 * 3 * 4"), 25);
+        assert_eq!(eval(&table, "2 ^ 3 ^ 2"), 512);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse_error("1 + 2 & 3");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('&'));
+        assert_eq!(
+            err.to_string(),
+            "Unexpected character '&'\n1 + 2 & 3\n      ^"
+        );
+
+        let err = parse_error("2 * (3 + 4");
+        assert_eq!(err.kind, ParseErrorKind::UnclosedParen);
+        assert_eq!(err.to_string(), "Unclosed parenthesis\n2 * (3 + 4\n    ^");
+
+        let err = parse_error("2 * (3 4) + 5");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedToken(Token::Num(4)));
+        assert_eq!(err.span, Span::new(7, 8));
+
+        let err = parse_error("(1 + 2)) * 3");
+        assert_eq!(err.kind, ParseErrorKind::UnmatchedParen);
+        assert_eq!(err.span, Span::new(7, 8));
+
+        let err = parse_error("1 + 2 3 4");
+        assert_eq!(err.kind, ParseErrorKind::TrailingInput);
+        assert_eq!(
+            err.to_string(),
+            "Unexpected input after expression\n1 + 2 3 4\n      ^^^"
+        );
+
+        let err = parse_error("1 +");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd);
+        assert_eq!(err.span, Span::new(3, 4));
+
+        let err = parse_error("1 + * 2");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedToken(Token::Mul));
+
+        let err = parse_error("99999999999999999999");
+        assert_eq!(err.kind, ParseErrorKind::NumberTooLarge);
+
+        let err = parse_error("1 + é");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('é'));
+        assert_eq!(err.span, Span::new(4, 6));
+
+        assert!(parse_expr(&mut Tokens::new("  1 + 2  "), &parser1::table()).is_ok());
+    }
+
+    #[test]
+    fn error_in_input() {
+        let err = part1("1 + 2\n(3 * 4\n").unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to evaluate line 2");
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    use super::ast::{lookup, negate, Ast, EvalResult, Node, NodeId};
+    use super::*;
+    use test::Bencher;
+
+    /// The tree-walker from before the arena, one allocation per node, kept to compare
+    /// against
+    mod boxed {
+        use super::*;
+
+        #[derive(Debug)]
+        pub struct BinaryExpr {
+            op: BinaryOp,
+            lhs: Box<dyn Evaluable>,
+            rhs: Box<dyn Evaluable>,
+        }
+
+        impl Evaluable for BinaryExpr {
+            fn eval(&self, env: &Env) -> EvalResult {
+                self.op.apply(self.lhs.eval(env)?, self.rhs.eval(env)?)
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct Negate(Box<dyn Evaluable>);
+
+        impl Evaluable for Negate {
+            fn eval(&self, env: &Env) -> EvalResult {
+                negate(self.0.eval(env)?)
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct Literal(i64);
+
+        impl Evaluable for Literal {
+            fn eval(&self, _env: &Env) -> EvalResult {
+                Ok(self.0)
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct Var(String);
+
+        impl Evaluable for Var {
+            fn eval(&self, env: &Env) -> EvalResult {
+                lookup(env, &self.0)
+            }
+        }
+
+        /// Copies an arena tree into separately allocated nodes
+        pub fn from_ast(ast: &Ast, id: NodeId) -> Box<dyn Evaluable> {
+            match ast.node(id) {
+                Node::Literal(n) => Box::new(Literal(n)),
+                Node::Var(var) => Box::new(Var(ast.name(var).to_owned())),
+                Node::Negate(expr) => Box::new(Negate(from_ast(ast, expr))),
+                Node::Binary(op, lhs, rhs) => Box::new(BinaryExpr {
+                    op,
+                    lhs: from_ast(ast, lhs),
+                    rhs: from_ast(ast, rhs),
+                }),
+            }
+        }
+    }
+
+    const INPUT: &str = include_str!("../../input/day18");
+
+    fn parse_input() -> Vec<Ast> {
+        INPUT
+            .lines()
+            .map(|expr| parser2::parse_expr(&mut Tokens::new(expr)).unwrap())
+            .collect()
+    }
+
+    #[bench]
+    fn parse(b: &mut Bencher) {
+        b.iter(parse_input);
+    }
+
+    #[bench]
+    fn tree_walk(b: &mut Bencher) {
+        let asts = parse_input();
+
+        b.iter(|| {
+            asts.iter()
+                .map(|ast| ast.eval(&Env::new()).unwrap())
+                .sum::<i64>()
+        });
+    }
+
+    #[bench]
+    fn tree_walk_boxed(b: &mut Bencher) {
+        let exprs = parse_input()
+            .iter()
+            .map(|ast| boxed::from_ast(ast, ast.root()))
+            .collect::<Vec<_>>();
+
+        b.iter(|| {
+            exprs
+                .iter()
+                .map(|expr| expr.eval(&Env::new()).unwrap())
+                .sum::<i64>()
+        });
+    }
+
+    #[bench]
+    fn bytecode(b: &mut Bencher) {
+        let programs = parse_input()
+            .iter()
+            .map(Program::compile)
+            .collect::<Vec<_>>();
+
+        b.iter(|| {
+            programs
+                .iter()
+                .map(|p| p.eval(&Env::new()).unwrap())
+                .sum::<i64>()
+        });
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use super::ast::{Ast, BinaryOp, Node, NodeId};
+use super::lexer::{ParseError, ParseErrorKind, Span, Token, Tokens};
+
+pub type ParseResult = Result<Ast, ParseError>;
+
+/// How deeply parentheses, negations and right-associative operators may nest, so that
+/// pathological input is reported as an error instead of overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+/// How many nodes the longest path from the root of a tree may have. Evaluating, compiling
+/// and printing a tree all recurse along its paths, and a long flat chain such as
+/// `1 + 1 + ... + 1` makes a tall tree without nesting at all.
+const MAX_HEIGHT: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+/// Binding power of each binary operator, operators with a higher precedence bind tighter
+#[derive(Debug, Clone, Default)]
+pub struct OperatorTable(HashMap<BinaryOp, (u8, Assoc)>);
+
+impl OperatorTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, op: BinaryOp, precedence: u8, assoc: Assoc) -> Self {
+        self.0.insert(op, (precedence, assoc));
+        self
+    }
+
+    fn get(&self, op: BinaryOp) -> Option<(u8, Assoc)> {
+        self.0.get(&op).copied()
+    }
+}
+
+/// A Pratt parser, where the operator table decides how tightly operators bind
+///
+/// Using the following grammar, where `op` is any operator in the table:
+///
+/// E : T, { op, T }
+///   ;
+/// T : N
+///   | V
+///   | "-" T
+///   | "(" E ")"
+///   ;
+/// N : number
+///   ;
+/// V : identifier
+///   ;
+///
+/// Unary negation binds tighter than any binary operator, so `-2 ^ 2` is `4`.
+///
+/// The whole input has to form a single expression, so unbalanced parentheses and any
+/// input left after the expression are reported as errors.
+pub fn parse_expr(tokens: &mut Tokens, table: &OperatorTable) -> ParseResult {
+    let mut ast = Ast::new();
+    parse_binary(tokens, table, &mut ast, 0, 0)?;
+
+    match tokens.advance()? {
+        None => Ok(ast),
+        Some((Token::ParenClose, span)) => Err(tokens.error(ParseErrorKind::UnmatchedParen, span)),
+        Some((_, span)) => {
+            let span = Span::new(span.start, tokens.end_span().start);
+            Err(tokens.error(ParseErrorKind::TrailingInput, span))
+        }
+    }
+}
+
+fn parse_binary(
+    tokens: &mut Tokens,
+    table: &OperatorTable,
+    ast: &mut Ast,
+    min_precedence: u16,
+    depth: usize,
+) -> Result<(NodeId, usize), ParseError> {
+    let (mut lhs, mut height) = parse_term(tokens, table, ast, depth)?;
+
+    while let Some((token, span)) = tokens.peek()? {
+        let op = match BinaryOp::from_token(&token) {
+            Some(op) => op,
+            None => break,
+        };
+
+        let (precedence, assoc) = table
+            .get(op)
+            .ok_or_else(|| tokens.error(ParseErrorKind::UnsupportedOperator(op), span))?;
+
+        // Widened, so that one past the highest precedence still fits
+        let precedence = u16::from(precedence);
+        if precedence < min_precedence {
+            break;
+        }
+
+        tokens.advance()?;
+
+        let next_precedence = match assoc {
+            Assoc::Left => precedence + 1,
+            Assoc::Right => precedence,
+        };
+
+        let (rhs, rhs_height) = parse_binary(tokens, table, ast, next_precedence, depth + 1)?;
+        height = grow(tokens, height.max(rhs_height), span)?;
+        lhs = ast.push(Node::Binary(op, lhs, rhs));
+    }
+
+    Ok((lhs, height))
+}
+
+fn parse_term(
+    tokens: &mut Tokens,
+    table: &OperatorTable,
+    ast: &mut Ast,
+    depth: usize,
+) -> Result<(NodeId, usize), ParseError> {
+    if depth > MAX_DEPTH {
+        let span = match tokens.peek()? {
+            Some((_, span)) => span,
+            None => tokens.end_span(),
+        };
+        return Err(tokens.error(ParseErrorKind::TooDeep, span));
+    }
+
+    match tokens.advance()? {
+        Some((Token::Num(n), _)) => Ok((ast.push(Node::Literal(n)), 1)),
+        Some((Token::Ident(name), _)) => Ok((ast.var(&name), 1)),
+        Some((Token::Sub, span)) => {
+            let (expr, height) = parse_term(tokens, table, ast, depth + 1)?;
+            Ok((ast.push(Node::Negate(expr)), grow(tokens, height, span)?))
+        }
+        Some((Token::ParenOpen, open)) => {
+            let expr = parse_binary(tokens, table, ast, 0, depth + 1)?;
+
+            match tokens.advance()? {
+                Some((Token::ParenClose, _)) => Ok(expr),
+                Some((token, span)) => {
+                    Err(tokens.error(ParseErrorKind::UnexpectedToken(token), span))
+                }
+                None => Err(tokens.error(ParseErrorKind::UnclosedParen, open)),
+            }
+        }
+        Some((token, span)) => Err(tokens.error(ParseErrorKind::UnexpectedToken(token), span)),
+        None => Err(tokens.error(ParseErrorKind::UnexpectedEnd, tokens.end_span())),
+    }
+}
+
+/// The height of a node whose tallest child has the given height, pointing at the
+/// operator of the node if the tree gets too tall
+fn grow(tokens: &Tokens, height: usize, span: Span) -> Result<usize, ParseError> {
+    if height >= MAX_HEIGHT {
+        return Err(tokens.error(ParseErrorKind::TooDeep, span));
+    }
+
+    Ok(height + 1)
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+use super::ast::{Ast, Node, NodeId};
+
+/// Displays an expression with every operation wrapped in parentheses, e.g. `(2 * (3 + 4))`
+pub struct Parenthesized<'a>(pub &'a Ast);
+
+/// Displays an expression as an S-expression, e.g. `(* 2 (+ 3 4))`
+pub struct SExpr<'a>(pub &'a Ast);
+
+impl Parenthesized<'_> {
+    fn fmt_node(&self, f: &mut fmt::Formatter, id: NodeId) -> fmt::Result {
+        match self.0.node(id) {
+            Node::Literal(n) => write!(f, "{}", n),
+            Node::Var(var) => write!(f, "{}", self.0.name(var)),
+            Node::Negate(expr) => {
+                write!(f, "(-")?;
+                self.fmt_node(f, expr)?;
+                write!(f, ")")
+            }
+            Node::Binary(op, lhs, rhs) => {
+                write!(f, "(")?;
+                self.fmt_node(f, lhs)?;
+                write!(f, " {} ", op)?;
+                self.fmt_node(f, rhs)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Parenthesized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_node(f, self.0.root())
+    }
+}
+
+impl SExpr<'_> {
+    fn fmt_node(&self, f: &mut fmt::Formatter, id: NodeId) -> fmt::Result {
+        match self.0.node(id) {
+            Node::Literal(n) => write!(f, "{}", n),
+            Node::Var(var) => write!(f, "{}", self.0.name(var)),
+            Node::Negate(expr) => {
+                write!(f, "(- ")?;
+                self.fmt_node(f, expr)?;
+                write!(f, ")")
+            }
+            Node::Binary(op, lhs, rhs) => {
+                write!(f, "({} ", op)?;
+                self.fmt_node(f, lhs)?;
+                write!(f, " ")?;
+                self.fmt_node(f, rhs)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for SExpr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_node(f, self.0.root())
+    }
+}
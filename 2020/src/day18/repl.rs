@@ -0,0 +1,169 @@
+use std::io::{self, BufRead, Write};
+
+use super::ast::{Ast, Env, EvalError, Evaluable};
+use super::lexer::Tokens;
+use super::parser::{parse_expr, OperatorTable};
+use super::pretty::{Parenthesized, SExpr};
+use super::symbolic::solve;
+use super::{parser1, parser2};
+
+const HELP: &str = "\
+Type an expression to see how it is parsed and evaluated, or one of these commands:
+  :mode 1     only show the result without operator precedence
+  :mode 2     only show the result with addition before multiplication
+  :mode both  show the results of both parts
+  :parens     print the parsed tree with explicit parentheses
+  :sexpr      print the parsed tree as an S-expression
+  :let x = 3  bind a variable, expressions with unbound variables are simplified instead
+  :solve x 2 * x + 3 = 11
+              solve a linear equation for a single unknown
+  :help       show this message
+  :quit       leave the session";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Part1,
+    Part2,
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Parenthesized,
+    SExpr,
+}
+
+/// Reads expressions line by line and shows how each precedence mode parses and evaluates them
+pub fn repl(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut mode = Mode::Both;
+    let mut format = Format::Parenthesized;
+    let mut env = Env::new();
+
+    writeln!(output, "Type :help for a list of commands")?;
+    prompt(&mut output)?;
+
+    for line in input.lines() {
+        let line = line?;
+
+        match line.trim() {
+            "" => {}
+            ":quit" | ":q" => break,
+            ":help" => writeln!(output, "{}", HELP)?,
+            ":mode 1" => mode = Mode::Part1,
+            ":mode 2" => mode = Mode::Part2,
+            ":mode both" => mode = Mode::Both,
+            ":parens" => format = Format::Parenthesized,
+            ":sexpr" => format = Format::SExpr,
+            cmd if cmd.starts_with(":let ") => match bind(&mut env, &cmd[5..]) {
+                Ok((name, value)) => writeln!(output, "{} = {}", name, value)?,
+                Err(err) => writeln!(output, "{}", err)?,
+            },
+            cmd if cmd.starts_with(":solve ") => {
+                for (name, table) in tables(mode) {
+                    match solve_equation(&table, &env, &cmd[7..]) {
+                        Ok((unknown, value)) => {
+                            writeln!(output, "{}: {} = {}", name, unknown, value)?
+                        }
+                        Err(err) => writeln!(output, "{}: {}", name, err)?,
+                    }
+                }
+            }
+            cmd if cmd.starts_with(':') => writeln!(output, "Unknown command: {}", cmd)?,
+            expr => {
+                for (name, table) in tables(mode) {
+                    show(&mut output, name, &table, expr, format, &env)?;
+                }
+            }
+        }
+
+        prompt(&mut output)?;
+    }
+
+    Ok(())
+}
+
+fn prompt(output: &mut impl Write) -> io::Result<()> {
+    write!(output, "> ")?;
+    output.flush()
+}
+
+fn tables(mode: Mode) -> Vec<(&'static str, OperatorTable)> {
+    let mut tables = Vec::new();
+
+    if mode != Mode::Part2 {
+        tables.push(("part 1", parser1::table()));
+    }
+    if mode != Mode::Part1 {
+        tables.push(("part 2", parser2::table()));
+    }
+
+    tables
+}
+
+fn print(ast: &Ast, format: Format) -> String {
+    match format {
+        Format::Parenthesized => Parenthesized(ast).to_string(),
+        Format::SExpr => SExpr(ast).to_string(),
+    }
+}
+
+/// Binds `name = expr`, evaluating the expression with addition first
+fn bind(env: &mut Env, binding: &str) -> anyhow::Result<(String, i64)> {
+    let (name, expr) = binding
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected a binding like :let x = 3"))?;
+    let name = name.trim();
+
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        anyhow::bail!("Invalid variable name: {}", name);
+    }
+
+    let value = parser2::parse_expr(&mut Tokens::new(expr))?.eval(env)?;
+    env.insert(name.to_owned(), value);
+
+    Ok((name.to_owned(), value))
+}
+
+/// Solves `unknown lhs = rhs`
+fn solve_equation(
+    table: &OperatorTable,
+    env: &Env,
+    equation: &str,
+) -> anyhow::Result<(String, i64)> {
+    let usage = || anyhow::anyhow!("Expected an equation like :solve x 2 * x + 3 = 11");
+
+    let (unknown, equation) = equation.trim().split_once(' ').ok_or_else(usage)?;
+    let (lhs, rhs) = equation.split_once('=').ok_or_else(usage)?;
+
+    let lhs = parse_expr(&mut Tokens::new(lhs), table)?;
+    let rhs = parse_expr(&mut Tokens::new(rhs), table)?;
+
+    Ok((unknown.to_owned(), solve(&lhs, &rhs, unknown, env)?))
+}
+
+fn show(
+    output: &mut impl Write,
+    name: &str,
+    table: &OperatorTable,
+    expr: &str,
+    format: Format,
+    env: &Env,
+) -> io::Result<()> {
+    let ast = match parse_expr(&mut Tokens::new(expr), table) {
+        Ok(ast) => ast,
+        Err(err) => return writeln!(output, "{}: {}", name, err),
+    };
+
+    let result = match ast.eval(env) {
+        Ok(value) => value.to_string(),
+        Err(EvalError::UnboundVariable(_)) => match ast.fold(env) {
+            Ok(folded) => print(&folded, format),
+            Err(err) => err.to_string(),
+        },
+        Err(err) => err.to_string(),
+    };
+
+    writeln!(output, "{}: {} = {}", name, print(&ast, format), result)
+}
@@ -0,0 +1,284 @@
+use thiserror::Error;
+
+use std::convert::TryFrom;
+
+use super::ast::{lookup, negate, Ast, BinaryOp, Env, EvalError, Evaluable, Node, NodeId};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+    #[error("Equation is not linear in {0}")]
+    NonLinear(String),
+    #[error("Equation does not have a single solution for {0}")]
+    NoUniqueSolution(String),
+    #[error("Equation does not have an integer solution for {0}")]
+    NoIntegerSolution(String),
+}
+
+enum Folded {
+    Const(i64),
+    Expr(NodeId),
+}
+
+impl Folded {
+    fn into_node(self, ast: &mut Ast) -> NodeId {
+        match self {
+            Folded::Const(n) => ast.push(Node::Literal(n)),
+            Folded::Expr(id) => id,
+        }
+    }
+}
+
+impl Ast {
+    /// Substitutes the variables that are bound in `env` and evaluates every subexpression
+    /// that no longer depends on a variable, e.g. `x * (2 + 3)` becomes `x * 5`
+    pub fn fold(&self, env: &Env) -> Result<Ast, EvalError> {
+        let mut folded = Ast::new();
+        self.fold_node(self.root(), env, &mut folded)?
+            .into_node(&mut folded);
+
+        Ok(folded)
+    }
+
+    fn fold_node(&self, id: NodeId, env: &Env, out: &mut Ast) -> Result<Folded, EvalError> {
+        let folded = match self.node(id) {
+            Node::Literal(n) => Folded::Const(n),
+            Node::Var(var) => match env.get(self.name(var)) {
+                Some(&n) => Folded::Const(n),
+                None => Folded::Expr(out.var(self.name(var))),
+            },
+            Node::Negate(expr) => match self.fold_node(expr, env, out)? {
+                Folded::Const(n) => Folded::Const(negate(n)?),
+                Folded::Expr(expr) => Folded::Expr(out.push(Node::Negate(expr))),
+            },
+            Node::Binary(op, lhs, rhs) => {
+                match (
+                    self.fold_node(lhs, env, out)?,
+                    self.fold_node(rhs, env, out)?,
+                ) {
+                    (Folded::Const(lhs), Folded::Const(rhs)) => Folded::Const(op.apply(lhs, rhs)?),
+                    (lhs, rhs) => {
+                        let lhs = lhs.into_node(out);
+                        let rhs = rhs.into_node(out);
+                        Folded::Expr(out.push(Node::Binary(op, lhs, rhs)))
+                    }
+                }
+            }
+        };
+
+        Ok(folded)
+    }
+}
+
+/// A fraction in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+impl Ratio {
+    fn int(n: i64) -> Self {
+        Self {
+            num: n as i128,
+            den: 1,
+        }
+    }
+
+    fn new(num: i128, den: i128) -> Result<Self, EvalError> {
+        if den == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+
+        let gcd = gcd(num, den);
+        let sign = if den < 0 { -1 } else { 1 };
+
+        Ok(Self {
+            num: sign * num / gcd,
+            den: sign * den / gcd,
+        })
+    }
+
+    fn is_zero(self) -> bool {
+        self.num == 0
+    }
+
+    /// The value as an integer, if the denominator is one and it fits in an `i64`
+    fn to_int(self) -> Option<i64> {
+        if self.den == 1 {
+            i64::try_from(self.num).ok()
+        } else {
+            None
+        }
+    }
+
+    fn add(self, other: Self) -> Result<Self, EvalError> {
+        let num = self
+            .num
+            .checked_mul(other.den)
+            .zip(other.num.checked_mul(self.den))
+            .and_then(|(a, b)| a.checked_add(b));
+        let den = self.den.checked_mul(other.den);
+
+        Self::new(
+            num.ok_or(EvalError::Overflow)?,
+            den.ok_or(EvalError::Overflow)?,
+        )
+    }
+
+    fn neg(self) -> Self {
+        Self {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+
+    fn mul(self, other: Self) -> Result<Self, EvalError> {
+        let num = self.num.checked_mul(other.num);
+        let den = self.den.checked_mul(other.den);
+
+        Self::new(
+            num.ok_or(EvalError::Overflow)?,
+            den.ok_or(EvalError::Overflow)?,
+        )
+    }
+
+    fn div(self, other: Self) -> Result<Self, EvalError> {
+        self.mul(Self::new(other.den, other.num)?)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a.max(1)
+}
+
+/// `coef * x + constant`, where `x` is the unknown. Parts that do not depend on the unknown
+/// always have a `coef` of zero and an integer `constant`.
+#[derive(Debug, Clone, Copy)]
+struct Linear {
+    coef: Ratio,
+    constant: Ratio,
+}
+
+impl Linear {
+    fn constant(n: i64) -> Self {
+        Self {
+            coef: Ratio::int(0),
+            constant: Ratio::int(n),
+        }
+    }
+
+    fn as_constant(self) -> Option<i64> {
+        if self.coef.is_zero() {
+            self.constant.to_int()
+        } else {
+            None
+        }
+    }
+
+    fn scale(self, factor: Ratio) -> Result<Self, EvalError> {
+        Ok(Self {
+            coef: self.coef.mul(factor)?,
+            constant: self.constant.mul(factor)?,
+        })
+    }
+
+    fn add(self, other: Self) -> Result<Self, EvalError> {
+        Ok(Self {
+            coef: self.coef.add(other.coef)?,
+            constant: self.constant.add(other.constant)?,
+        })
+    }
+
+    fn neg(self) -> Self {
+        Self {
+            coef: self.coef.neg(),
+            constant: self.constant.neg(),
+        }
+    }
+}
+
+impl Ast {
+    fn linear(&self, id: NodeId, unknown: &str, env: &Env) -> Result<Linear, SolveError> {
+        let non_linear = || SolveError::NonLinear(unknown.to_owned());
+
+        let linear = match self.node(id) {
+            Node::Literal(n) => Linear::constant(n),
+            Node::Var(var) if self.name(var) == unknown => Linear {
+                coef: Ratio::int(1),
+                constant: Ratio::int(0),
+            },
+            Node::Var(var) => Linear::constant(lookup(env, self.name(var))?),
+            Node::Negate(expr) => self.linear(expr, unknown, env)?.neg(),
+            Node::Binary(op, lhs, rhs) => {
+                let lhs = self.linear(lhs, unknown, env)?;
+                let rhs = self.linear(rhs, unknown, env)?;
+
+                // Constant parts follow the integer semantics of evaluation
+                if let (Some(a), Some(b)) = (lhs.as_constant(), rhs.as_constant()) {
+                    return Ok(Linear::constant(op.apply(a, b)?));
+                }
+
+                match op {
+                    BinaryOp::Add => lhs.add(rhs)?,
+                    BinaryOp::Sub => lhs.add(rhs.neg())?,
+                    BinaryOp::Mul if lhs.coef.is_zero() => rhs.scale(lhs.constant)?,
+                    BinaryOp::Mul if rhs.coef.is_zero() => lhs.scale(rhs.constant)?,
+                    BinaryOp::Div if rhs.coef.is_zero() => {
+                        lhs.scale(Ratio::int(1).div(rhs.constant)?)?
+                    }
+                    BinaryOp::Pow => match rhs.as_constant() {
+                        Some(0) => Linear::constant(1),
+                        Some(1) => lhs,
+                        _ => return Err(non_linear()),
+                    },
+                    _ => return Err(non_linear()),
+                }
+            }
+        };
+
+        Ok(linear)
+    }
+}
+
+/// Finds the value of `unknown` for which `lhs` equals `rhs`, where both sides are linear in
+/// the unknown and every other variable is bound in `env`.
+///
+/// Division by a constant is solved exactly, so the solution is checked against the
+/// truncating division of regular evaluation before it is returned.
+pub fn solve(lhs: &Ast, rhs: &Ast, unknown: &str, env: &Env) -> Result<i64, SolveError> {
+    let left = lhs.linear(lhs.root(), unknown, env)?;
+    let right = rhs.linear(rhs.root(), unknown, env)?;
+
+    // coef * x + constant = 0
+    let diff = left.add(right.neg())?;
+
+    if diff.coef.is_zero() {
+        return Err(SolveError::NoUniqueSolution(unknown.to_owned()));
+    }
+
+    let no_integer_solution = || SolveError::NoIntegerSolution(unknown.to_owned());
+
+    let x = diff
+        .constant
+        .neg()
+        .div(diff.coef)?
+        .to_int()
+        .ok_or_else(no_integer_solution)?;
+
+    let mut env = env.clone();
+    env.insert(unknown.to_owned(), x);
+
+    if lhs.eval(&env)? == rhs.eval(&env)? {
+        Ok(x)
+    } else {
+        Err(no_integer_solution())
+    }
+}
@@ -1,114 +1,275 @@
-use anyhow::Context;
+use anyhow::{bail, Context};
+use regex::Regex;
 
-struct PasswordPolicy {
+use std::{collections::HashSet, fmt, str::FromStr};
+
+/// A password along with the numbers and letter of the policy it was given with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
     password: String,
-    repeat_min: usize,
-    repeat_max: usize,
-    repeat_char: u8,
+    first: usize,
+    second: usize,
+    letter: u8,
+}
+
+/// A rule a password has to follow. Each one decides how to use the numbers and letter given
+/// with a password, if at all.
+pub trait Policy: fmt::Debug + fmt::Display {
+    /// Why the password breaks this policy, if it does
+    fn check(&self, entry: &PasswordPolicy) -> Option<String>;
 }
 
-impl PasswordPolicy {
-    fn is_valid_1(&self) -> bool {
-        let char_count = self
+/// The letter has to appear between `first` and `second` times
+#[derive(Debug, Clone, Copy)]
+pub struct CountRange;
+
+impl Policy for CountRange {
+    fn check(&self, entry: &PasswordPolicy) -> Option<String> {
+        let count = entry
             .password
             .bytes()
-            .filter(|&b| b == self.repeat_char)
+            .filter(|&b| b == entry.letter)
             .count();
 
-        self.repeat_min <= char_count && char_count <= self.repeat_max
+        if entry.first <= count && count <= entry.second {
+            None
+        } else {
+            Some(format!(
+                "'{}' appears {} times, not {} to {}",
+                entry.letter as char, count, entry.first, entry.second
+            ))
+        }
     }
+}
 
-    fn is_valid_2(&self) -> bool {
-        let has_char_at_min = self
-            .password
-            .bytes()
-            .nth(self.repeat_min - 1)
-            .map_or(false, |b| b == self.repeat_char);
-        let has_char_at_max = self
+impl fmt::Display for CountRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "count")
+    }
+}
+
+/// The letter has to be at exactly one of the two positions, which start at one. Position
+/// zero does not exist, so it never has the letter.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionalXor;
+
+impl Policy for PositionalXor {
+    fn check(&self, entry: &PasswordPolicy) -> Option<String> {
+        let has_letter = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|i| entry.password.as_bytes().get(i))
+                == Some(&entry.letter)
+        };
+
+        match (has_letter(entry.first), has_letter(entry.second)) {
+            (true, false) | (false, true) => None,
+            (has, _) => Some(format!(
+                "'{}' is at {} of positions {} and {}",
+                entry.letter as char,
+                if has { "both" } else { "neither" },
+                entry.first,
+                entry.second
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PositionalXor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "position")
+    }
+}
+
+/// None of the characters may appear in the password
+#[derive(Debug, Clone)]
+pub struct Forbidden(String);
+
+impl Policy for Forbidden {
+    fn check(&self, entry: &PasswordPolicy) -> Option<String> {
+        let found = entry
             .password
-            .bytes()
-            .nth(self.repeat_max - 1)
-            .map_or(false, |b| b == self.repeat_char);
+            .chars()
+            .filter(|&c| self.0.contains(c))
+            .collect::<String>();
+
+        if found.is_empty() {
+            None
+        } else {
+            Some(format!("contains forbidden characters '{}'", found))
+        }
+    }
+}
 
-        (has_char_at_min || has_char_at_max) && !(has_char_at_min && has_char_at_max)
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "forbidden:{}", self.0)
     }
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let valid_passwords = input
-        .lines()
-        .filter_map(parse_password_policy)
-        .filter(|p| p.is_valid_1())
-        .count();
+/// The password needs at least this many different characters
+#[derive(Debug, Clone, Copy)]
+pub struct MinDistinct(usize);
+
+impl Policy for MinDistinct {
+    fn check(&self, entry: &PasswordPolicy) -> Option<String> {
+        let distinct = entry.password.chars().collect::<HashSet<_>>().len();
 
-    Ok(valid_passwords)
+        if distinct >= self.0 {
+            None
+        } else {
+            Some(format!(
+                "has {} distinct characters, not {}",
+                distinct, self.0
+            ))
+        }
+    }
 }
 
-fn parse_password_policy(s: &str) -> Option<PasswordPolicy> {
-    let (rest, (repeat_min, repeat_max)) = parse_repeat_minmax(&s).ok()?;
-    let (rest, repeat_char) = parse_repeat_char(&rest).ok()?;
-    let password = parse_password(&rest).ok()?;
+impl fmt::Display for MinDistinct {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "distinct:{}", self.0)
+    }
+}
 
-    Some(PasswordPolicy {
-        password,
-        repeat_min,
-        repeat_max,
-        repeat_char,
-    })
+/// The password has to match the regex somewhere, anchor it to match the whole password
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Policy for Pattern {
+    fn check(&self, entry: &PasswordPolicy) -> Option<String> {
+        if self.0.is_match(&entry.password) {
+            None
+        } else {
+            Some(format!("does not match {}", self.0))
+        }
+    }
 }
 
-fn parse_password(s: &str) -> anyhow::Result<String> {
-    let (_, password) = take_while(&s, |&b| matches!(b, b'a'..=b'z'));
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "regex:{}", self.0)
+    }
+}
 
-    Ok(password)
+/// Policies are selected as `count`, `position`, `forbidden:<chars>`, `distinct:<n>` or
+/// `regex:<pattern>`
+impl FromStr for Box<dyn Policy> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = s.split_once(':').unwrap_or((s, ""));
+
+        let policy: Box<dyn Policy> = match (kind, arg) {
+            ("count", "") => Box::new(CountRange),
+            ("position", "") => Box::new(PositionalXor),
+            ("forbidden", chars) if !chars.is_empty() => Box::new(Forbidden(chars.to_owned())),
+            ("distinct", n) => Box::new(MinDistinct(
+                n.parse()
+                    .with_context(|| format!("Invalid count '{}'", n))?,
+            )),
+            ("regex", pattern) => Box::new(Pattern(Regex::new(pattern)?)),
+            _ => bail!(
+                "Unknown policy '{}'; must be count, position, forbidden:<chars>, \
+                 distinct:<n> or regex:<pattern>",
+                s
+            ),
+        };
+
+        Ok(policy)
+    }
 }
 
-fn parse_repeat_char(s: &str) -> anyhow::Result<(&str, u8)> {
-    let (rest, repeat_char) = take_while(&s, |&b| matches!(b, b'a'..=b'z'));
-    let repeat_char = repeat_char
-        .bytes()
-        .next()
-        .context("No character was found")?;
+/// Parses a line such as `1-3 a: abcde`
+fn parse_password_policy(s: &str) -> anyhow::Result<PasswordPolicy> {
+    let (policy, password) = s
+        .split_once(": ")
+        .context("Expected ': ' before the password")?;
+    let (range, letter) = policy
+        .split_once(' ')
+        .context("Expected a space before the letter")?;
+    let (first, second) = range.split_once('-').context("Expected a range like 1-3")?;
+
+    let letter = match letter.as_bytes() {
+        &[b] if b.is_ascii_graphic() => b,
+        _ => bail!("Expected a single letter, not '{}'", letter),
+    };
 
-    let rest = consume_while(&rest, |&b| matches!(b, b':' | b' '));
+    if password.is_empty() || password.contains(char::is_whitespace) {
+        bail!("Invalid password '{}'", password);
+    }
 
-    Ok((rest, repeat_char))
+    Ok(PasswordPolicy {
+        password: password.to_owned(),
+        first: first
+            .parse()
+            .with_context(|| format!("Invalid number '{}'", first))?,
+        second: second
+            .parse()
+            .with_context(|| format!("Invalid number '{}'", second))?,
+        letter,
+    })
 }
 
-fn parse_repeat_minmax(s: &str) -> anyhow::Result<(&str, (usize, usize))> {
-    let (rest, repeat_min) = take_while(&s, |&b| matches!(b, b'0'..=b'9'));
-    let repeat_min = repeat_min.parse::<usize>()?;
+/// Counts the passwords that follow every policy, printing the ones that do not if asked to
+fn count_valid(
+    input: &str,
+    policies: &[&dyn Policy],
+    show_rejected: bool,
+) -> anyhow::Result<usize> {
+    let mut valid = 0;
 
-    let rest = consume_while(&rest, |&b| matches!(b, b'-'));
+    for (i, line) in input.lines().enumerate() {
+        let entry = parse_password_policy(line)
+            .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))?;
 
-    let (rest, repeat_max) = take_while(&rest, |&b| matches!(b, b'0'..=b'9'));
-    let repeat_max = repeat_max.parse::<usize>()?;
+        let mut broken = policies
+            .iter()
+            .filter_map(|policy| policy.check(&entry).map(|reason| (policy, reason)))
+            .peekable();
 
-    let rest = consume_while(&rest, |&b| matches!(b, b' '));
+        if broken.peek().is_none() {
+            valid += 1;
+        } else if show_rejected {
+            for (policy, reason) in broken {
+                println!(
+                    "line {}: '{}' breaks {}: {}",
+                    i + 1,
+                    entry.password,
+                    policy,
+                    reason
+                );
+            }
+        }
+    }
 
-    Ok((rest, (repeat_min, repeat_max)))
+    Ok(valid)
 }
 
-fn take_while<F: Fn(&u8) -> bool>(s: &str, cond: F) -> (&str, String) {
-    let take = s
-        .bytes()
-        .take_while(cond)
-        .map(char::from)
-        .collect::<String>();
-    (&s[take.len()..], take)
+/// Uses the count policy, unless others were selected
+pub fn part1(
+    input: &str,
+    policies: &[Box<dyn Policy>],
+    show_rejected: bool,
+) -> anyhow::Result<usize> {
+    let policies = select(policies, &CountRange);
+    count_valid(input, &policies, show_rejected)
 }
 
-fn consume_while<F: Fn(&u8) -> bool>(s: &str, cond: F) -> &str {
-    let consumed = s.bytes().take_while(cond).count();
-    &s[consumed..]
+/// Uses the position policy, unless others were selected
+pub fn part2(
+    input: &str,
+    policies: &[Box<dyn Policy>],
+    show_rejected: bool,
+) -> anyhow::Result<usize> {
+    let policies = select(policies, &PositionalXor);
+    count_valid(input, &policies, show_rejected)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let valid_passwords = input
-        .lines()
-        .filter_map(parse_password_policy)
-        .filter(|p| p.is_valid_2())
-        .count();
-
-    Ok(valid_passwords)
+fn select<'a>(policies: &'a [Box<dyn Policy>], default: &'a dyn Policy) -> Vec<&'a dyn Policy> {
+    if policies.is_empty() {
+        vec![default]
+    } else {
+        policies.iter().map(|p| p.as_ref()).collect()
+    }
 }
@@ -1,28 +1,276 @@
-fn count_trees(input: &str, down: usize, right: usize) -> usize {
-    input
-        .lines()
-        .step_by(down)
-        .enumerate()
-        .filter(|(i, row)| {
-            row.chars()
-                .cycle()
-                .nth(i * right)
-                .map_or(false, |c| c == '#')
+use anyhow::{bail, Context};
+
+use std::{collections::HashSet, fmt, ops::Range, str::FromStr};
+
+use crate::visualize::{Glyph, Render, Visualizer};
+
+/// The trees of a slope, which repeats itself to the right and left forever
+#[derive(Debug, Clone)]
+pub struct TreeMap {
+    width: usize,
+    height: usize,
+    trees: Vec<bool>,
+}
+
+impl TreeMap {
+    pub fn is_tree(&self, row: usize, col: i64) -> bool {
+        let col = col.rem_euclid(self.width as i64) as usize;
+        self.trees[row * self.width + col]
+    }
+}
+
+impl FromStr for TreeMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let width = s.lines().next().map_or(0, str::len);
+        if width == 0 {
+            bail!("The map is empty");
+        }
+
+        let mut trees = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            if line.len() != width {
+                bail!("Line {} is {} wide instead of {}", i + 1, line.len(), width);
+            }
+
+            for c in line.chars() {
+                trees.push(match c {
+                    '#' => true,
+                    '.' => false,
+                    c => bail!("Invalid char on line {}: {:?}", i + 1, c),
+                });
+            }
+        }
+
+        Ok(Self {
+            width,
+            height: trees.len() / width,
+            trees,
         })
-        .count()
+    }
+}
+
+/// Columns to move right for every `down` rows. Fractional moves such as `1/2,1` are
+/// written as whole ones over more rows, `1,2` in this case, and negative moves go left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slope {
+    right: i64,
+    down: usize,
+}
+
+impl Slope {
+    pub fn new(right: i64, down: usize) -> Self {
+        Self { right, down }
+    }
+
+    /// Every square the toboggan lands on, from the top left to the bottom of the map
+    fn path(self, map: &TreeMap) -> impl Iterator<Item = (usize, i64)> {
+        (0..map.height)
+            .step_by(self.down)
+            .enumerate()
+            .map(move |(i, row)| (row, i as i64 * self.right))
+    }
+
+    pub fn count_trees(self, map: &TreeMap) -> usize {
+        self.path(map)
+            .filter(|&(row, col)| map.is_tree(row, col))
+            .count()
+    }
+}
+
+impl FromStr for Slope {
+    type Err = anyhow::Error;
+
+    /// Parses `right,down`, where `right` may be a fraction such as `1/2`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (right, down) = s
+            .split_once(',')
+            .with_context(|| format!("Expected right,down instead of '{}'", s))?;
+
+        let (right, denominator) = right.split_once('/').unwrap_or((right, "1"));
+        let right = right
+            .trim()
+            .parse::<i64>()
+            .with_context(|| format!("Invalid move right '{}'", right))?;
+        let denominator = denominator
+            .trim()
+            .parse::<usize>()
+            .with_context(|| format!("Invalid denominator '{}'", denominator))?;
+        let down = down
+            .trim()
+            .parse::<usize>()
+            .with_context(|| format!("Invalid move down '{}'", down))?;
+
+        if denominator == 0 || down == 0 {
+            bail!("Slope '{}' has to move down", s);
+        }
+
+        // `2/4,1` moves as far as `1/2,1`, so both go right 1 for every 2 rows
+        let divisor = gcd(right.unsigned_abs() as usize, denominator);
+        let down = (denominator / divisor)
+            .checked_mul(down)
+            .with_context(|| format!("Slope '{}' moves down too far", s))?;
+
+        // The divisor may not fit in an `i64` when `right` is `i64::MIN`
+        Ok(Self::new((right as i128 / divisor as i128) as i64, down))
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "right {}, down {}", self.right, self.down)
+    }
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    Ok(count_trees(input, 1, 3))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Square {
+    Open,
+    Tree,
+    /// Open and landed on
+    Passed,
+    Hit,
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let tree_count = [(1, 1), (1, 3), (1, 5), (1, 7), (2, 1)]
+impl Glyph for Square {
+    fn glyph(&self) -> char {
+        match self {
+            Square::Open => '.',
+            Square::Tree => '#',
+            Square::Passed => 'O',
+            Square::Hit => 'X',
+        }
+    }
+
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            Square::Open => [230, 230, 230],
+            Square::Tree => [40, 140, 60],
+            Square::Passed => [60, 120, 220],
+            Square::Hit => [220, 60, 60],
+        }
+    }
+}
+
+/// The map with the path of a slope marked on it, repeated as often as the path needs, like
+/// in the puzzle text
+struct PathView<'a> {
+    map: &'a TreeMap,
+    landed: HashSet<(usize, i64)>,
+    /// Columns shown, from the start of a repetition of the map to the end of one
+    cols: Range<i64>,
+}
+
+impl<'a> PathView<'a> {
+    fn new(map: &'a TreeMap, slope: Slope, cols: Range<i64>) -> Self {
+        Self {
+            map,
+            landed: slope.path(map).collect(),
+            cols,
+        }
+    }
+}
+
+/// Columns wide enough for the paths of all the slopes, so they can be drawn at the same size
+fn canvas(map: &TreeMap, slopes: &[Slope]) -> Range<i64> {
+    let width = map.width as i64;
+    let cols = slopes
         .iter()
-        .copied()
-        .map(|(down, right)| count_trees(input, down, right))
-        .inspect(|x| println!("x: {:?}", x))
-        .product::<usize>();
+        .flat_map(|slope| slope.path(map).map(|(_, col)| col));
+
+    let (min, max) = cols.fold((0, 0), |(min, max), col| (min.min(col), max.max(col)));
+    min.div_euclid(width) * width..(max.div_euclid(width) + 1) * width
+}
+
+impl Render for PathView<'_> {
+    type Cell = Square;
+
+    fn size(&self) -> (usize, usize) {
+        (self.map.height, (self.cols.end - self.cols.start) as usize)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Square {
+        let col = self.cols.start + col as i64;
+
+        match (
+            self.map.is_tree(row, col),
+            self.landed.contains(&(row, col)),
+        ) {
+            (false, false) => Square::Open,
+            (true, false) => Square::Tree,
+            (false, true) => Square::Passed,
+            (true, true) => Square::Hit,
+        }
+    }
+}
+
+/// Slopes moving at most `bounds.right` columns either way and at most `bounds.down` rows,
+/// with the fewest and the most trees
+fn search(map: &TreeMap, bounds: Slope) -> Option<((Slope, usize), (Slope, usize))> {
+    let max_right = bounds.right.abs();
+
+    let counts = (1..=bounds.down)
+        .flat_map(|down| (-max_right..=max_right).map(move |right| Slope::new(right, down)))
+        .map(|slope| (slope, slope.count_trees(map)))
+        .collect::<Vec<_>>();
+
+    let best = counts.iter().min_by_key(|(_, trees)| *trees)?;
+    let worst = counts.iter().max_by_key(|(_, trees)| *trees)?;
+
+    Some((*best, *worst))
+}
+
+pub fn part1(
+    input: &str,
+    slopes: &[Slope],
+    bounds: Option<Slope>,
+    visualizer: &mut Visualizer,
+) -> anyhow::Result<usize> {
+    let map = input.parse::<TreeMap>()?;
+
+    if let Some(((best, fewest), (worst, most))) = bounds.and_then(|b| search(&map, b)) {
+        println!("Fewest trees: {} at {}", fewest, best);
+        println!("Most trees: {} at {}", most, worst);
+    }
+
+    let slope = match slopes {
+        [] => Slope::new(3, 1),
+        [slope] => *slope,
+        _ => bail!(
+            "Part 1 follows a single slope, but {} were given",
+            slopes.len()
+        ),
+    };
+    visualizer.frame(&PathView::new(&map, slope, canvas(&map, &[slope])))?;
+
+    Ok(slope.count_trees(&map))
+}
+
+pub fn part2(input: &str, slopes: &[Slope], visualizer: &mut Visualizer) -> anyhow::Result<usize> {
+    let map = input.parse::<TreeMap>()?;
+
+    let defaults = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+        .iter()
+        .map(|&(right, down)| Slope::new(right, down))
+        .collect::<Vec<_>>();
+    let slopes = if slopes.is_empty() { &defaults } else { slopes };
+
+    let cols = canvas(&map, slopes);
+
+    let mut product = 1;
+    for &slope in slopes {
+        visualizer.frame(&PathView::new(&map, slope, cols.clone()))?;
+        product *= slope.count_trees(&map);
+    }
 
-    Ok(tree_count)
+    Ok(product)
 }
@@ -1,104 +1,495 @@
-use std::collections::HashSet;
-use std::iter::FromIterator;
-
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let count = input
-        .split("\n\n")
-        .filter(|s| {
-            let fields = s
-                .lines()
-                .flat_map(|line| line.split_whitespace().collect::<Vec<_>>())
-                .collect::<Vec<_>>();
+use serde::Serialize;
+use thiserror::Error;
 
-            validate_passport_1(&fields[..])
-        })
-        .count();
+use std::{fmt, str::FromStr};
 
-    Ok(count)
+use crate::schema::{Entry, Problem, Reason, Record, Rule, Schema, Value};
+
+fn schema() -> Schema {
+    Schema::new()
+        .required("byr", Rule::year(1920..=2002))
+        .required("iyr", Rule::year(2010..=2020))
+        .required("eyr", Rule::year(2020..=2030))
+        .required(
+            "hgt",
+            Rule::Measure(vec![("cm", 150..=193), ("in", 59..=76)]),
+        )
+        .required("hcl", Rule::pattern("#[0-9a-f]{6}"))
+        .required("ecl", Rule::OneOf(EYE_COLORS.to_vec()))
+        .required("pid", Rule::pattern("[0-9]{9}"))
+        .optional("cid", Rule::Any)
 }
 
-fn validate_passport_1(fields: &[&str]) -> bool {
-    let seen = HashSet::<String>::from_iter(fields.iter().map(|s| s[..3].to_string()));
-    (seen.len() == 8) || (seen.len() == 7 && !seen.contains("cid"))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let count = input
-        .split("\n\n")
-        .filter(|s| {
-            let fields = s
-                .lines()
-                .flat_map(|line| line.split_whitespace().collect::<Vec<_>>())
-                .collect::<Vec<_>>();
+#[derive(Debug, Clone, Error)]
+pub enum FormatError {
+    #[error("{0} is not a dump format; must be json or csv")]
+    BadFormat(String),
+}
 
-            validate_passport_2(&fields[..])
-        })
-        .count();
+impl FromStr for Format {
+    type Err = FormatError;
 
-    Ok(count)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            s => Err(FormatError::BadFormat(s.to_owned())),
+        }
+    }
+}
+
+/// A year, which the schema checks for its number of digits and range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Year(u32);
+
+impl FromStr for Year {
+    type Err = Reason;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_number(s).map(Year)
+    }
+}
+
+impl fmt::Display for Year {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Height {
+    Cm(u32),
+    In(u32),
+}
+
+impl FromStr for Height {
+    type Err = Reason;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        match unit {
+            "cm" => Ok(Height::Cm(parse_number(number)?)),
+            "in" => Ok(Height::In(parse_number(number)?)),
+            unit => Err(Reason::UnknownUnit {
+                unit: unit.to_owned(),
+                expected: vec!["cm", "in"],
+            }),
+        }
+    }
 }
 
-fn validate_passport_2(fields: &[&str]) -> bool {
-    let seen = HashSet::<String>::from_iter(fields.iter().map(|s| s[..3].to_string()));
-    let contains_fields = (seen.len() == 8) || (seen.len() == 7 && !seen.contains("cid"));
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Height::Cm(n) => write!(f, "{}cm", n),
+            Height::In(n) => write!(f, "{}in", n),
+        }
+    }
+}
+
+/// A colour, written as `#` followed by the rest of it, which the schema checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Color(String);
+
+impl FromStr for Color {
+    type Err = Reason;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('#') {
+            Ok(Color(s.to_owned()))
+        } else {
+            Err(Reason::Prefix('#'))
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+const EYE_COLORS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+
+/// A string of digits, which keeps its leading zeros
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PassportId(String);
 
-    if !contains_fields {
-        return false;
+impl FromStr for PassportId {
+    type Err = Reason;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+            Ok(PassportId(s.to_owned()))
+        } else {
+            Err(Reason::NotANumber)
+        }
+    }
+}
+
+impl fmt::Display for PassportId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn parse_number(s: &str) -> Result<u32, Reason> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Reason::NotANumber);
     }
 
-    fields.iter().all(|s| match &s[..3] {
-        "byr" => valid_birth_year(&s[4..]),
-        "iyr" => valid_issue_year(&s[4..]),
-        "eyr" => valid_expiration_year(&s[4..]),
-        "hgt" => valid_height(&s[4..]),
-        "hcl" => valid_hair_color(&s[4..]),
-        "ecl" => valid_eye_color(&s[4..]),
-        "pid" => valid_passport_id(&s[4..]),
-        "cid" => true,
-        _ => false,
+    // Only numbers that do not fit get here
+    s.parse().map_err(|_| Reason::OutOfRange {
+        min: 0,
+        max: u32::MAX,
     })
 }
 
-fn valid_birth_year(s: &str) -> bool {
-    s.parse::<u32>().map_or(false, |n| n >= 1920 && n <= 2002)
+/// A field that was present, even if its value could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Field<T> {
+    Parsed(T),
+    Malformed { malformed: String },
+}
+
+impl<T: fmt::Display> fmt::Display for Field<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::Parsed(value) => write!(f, "{}", value),
+            Field::Malformed { malformed } => write!(f, "{}", malformed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Passport {
+    pub byr: Option<Field<Year>>,
+    pub iyr: Option<Field<Year>>,
+    pub eyr: Option<Field<Year>>,
+    pub hgt: Option<Field<Height>>,
+    pub hcl: Option<Field<Color>>,
+    pub ecl: Option<Field<String>>,
+    pub pid: Option<Field<PassportId>>,
+    pub cid: Option<Field<String>>,
 }
 
-fn valid_issue_year(s: &str) -> bool {
-    s.parse::<u32>().map_or(false, |n| n >= 2010 && n <= 2020)
+/// Parses the value of a field, unless it was already given
+fn set<T: FromStr>(
+    slot: &mut Option<Field<T>>,
+    field: &str,
+    value: &str,
+    problems: &mut Vec<Problem>,
+) where
+    Reason: From<T::Err>,
+{
+    if slot.is_some() {
+        problems.push(Problem::Duplicate(field.to_owned()));
+        return;
+    }
+
+    *slot = Some(match value.parse() {
+        Ok(value) => Field::Parsed(value),
+        Err(reason) => {
+            problems.push(Problem::Invalid {
+                field: field.to_owned(),
+                value: value.to_owned(),
+                reason: reason.into(),
+            });
+            Field::Malformed {
+                malformed: value.to_owned(),
+            }
+        }
+    });
+}
+
+impl Passport {
+    /// Parses the `field:value` tokens of a single passport, along with everything that is
+    /// wrong with them
+    pub fn parse(tokens: &[&str]) -> (Self, Vec<Problem>) {
+        let mut passport = Self::default();
+        let mut problems = Vec::new();
+
+        for token in tokens {
+            let (field, value) = match token.split_once(':') {
+                Some(pair) => pair,
+                None => {
+                    problems.push(Problem::Malformed(token.to_string()));
+                    continue;
+                }
+            };
+
+            match field {
+                "byr" => set(&mut passport.byr, field, value, &mut problems),
+                "iyr" => set(&mut passport.iyr, field, value, &mut problems),
+                "eyr" => set(&mut passport.eyr, field, value, &mut problems),
+                "hgt" => set(&mut passport.hgt, field, value, &mut problems),
+                "hcl" => set(&mut passport.hcl, field, value, &mut problems),
+                "ecl" => set(&mut passport.ecl, field, value, &mut problems),
+                "pid" => set(&mut passport.pid, field, value, &mut problems),
+                "cid" => set(&mut passport.cid, field, value, &mut problems),
+                _ => problems.push(Problem::Unknown(field.to_owned())),
+            }
+        }
+
+        (passport, problems)
+    }
+
+    fn csv_row(&self, line: usize) -> String {
+        fn cell<T: fmt::Display>(field: &Option<Field<T>>) -> String {
+            let s = field.as_ref().map_or_else(String::new, |f| f.to_string());
+
+            if s.contains([',', '"']) {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s
+            }
+        }
+
+        [
+            line.to_string(),
+            cell(&self.byr),
+            cell(&self.iyr),
+            cell(&self.eyr),
+            cell(&self.hgt),
+            cell(&self.hcl),
+            cell(&self.ecl),
+            cell(&self.pid),
+            cell(&self.cid),
+        ]
+        .join(",")
+    }
+}
+
+fn entry<T>(field: &Option<Field<T>>, value: impl Fn(&T) -> Value) -> Entry<'_> {
+    match field {
+        None => Entry::Missing,
+        Some(Field::Malformed { .. }) => Entry::Malformed,
+        Some(Field::Parsed(v)) => Entry::Value(value(v)),
+    }
+}
+
+impl Record for Passport {
+    fn get(&self, field: &str) -> Entry<'_> {
+        match field {
+            "byr" => entry(&self.byr, |y| Value::Number(y.0)),
+            "iyr" => entry(&self.iyr, |y| Value::Number(y.0)),
+            "eyr" => entry(&self.eyr, |y| Value::Number(y.0)),
+            "hgt" => entry(&self.hgt, |h| match *h {
+                Height::Cm(n) => Value::Measure(n, "cm"),
+                Height::In(n) => Value::Measure(n, "in"),
+            }),
+            "hcl" => entry(&self.hcl, |c| Value::Text(&c.0)),
+            "ecl" => entry(&self.ecl, |e| Value::Text(e)),
+            "pid" => entry(&self.pid, |p| Value::Text(&p.0)),
+            "cid" => entry(&self.cid, |c| Value::Text(c)),
+            _ => Entry::Missing,
+        }
+    }
+}
+
+/// Blank line separated passports, with the line each of them starts on
+fn passports(input: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut passports = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            passports.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| (i + 1, Vec::new()))
+                .1
+                .extend(line.split_whitespace());
+        }
+    }
+
+    passports.extend(current);
+    passports
+}
+
+#[derive(Serialize)]
+struct Row<'a> {
+    line: usize,
+    #[serde(flatten)]
+    passport: &'a Passport,
 }
 
-fn valid_expiration_year(s: &str) -> bool {
-    s.parse::<u32>().map_or(false, |n| n >= 2020 && n <= 2030)
+fn dump(passports: &[(usize, Passport, Vec<Problem>)], format: Format) -> anyhow::Result<()> {
+    match format {
+        Format::Json => {
+            let rows = passports
+                .iter()
+                .map(|(line, passport, _)| Row {
+                    line: *line,
+                    passport,
+                })
+                .collect::<Vec<_>>();
+
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+        Format::Csv => {
+            println!("line,byr,iyr,eyr,hgt,hcl,ecl,pid,cid");
+            for (line, passport, _) in passports {
+                println!("{}", passport.csv_row(*line));
+            }
+        }
+    }
+
+    Ok(())
 }
 
-fn valid_height(s: &str) -> bool {
-    if s.len() <= 2 {
-        return false;
+/// Counts the passports without any problems that matter for this part, printing the
+/// rejected ones with their problems if asked to
+fn count_valid(
+    input: &str,
+    show_rejected: bool,
+    format: Option<Format>,
+    matters: fn(&Problem) -> bool,
+) -> anyhow::Result<usize> {
+    let schema = schema();
+    let passports = passports(input)
+        .into_iter()
+        .map(|(line, tokens)| {
+            let (passport, mut problems) = Passport::parse(&tokens);
+            problems.extend(schema.validate(&passport));
+            (line, passport, problems)
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(format) = format {
+        dump(&passports, format)?;
     }
 
-    match &s[s.len() - 2..] {
-        "cm" => s[..s.len() - 2]
-            .parse::<u32>()
-            .map_or(false, |n| n >= 150 && n <= 193),
-        "in" => s[..s.len() - 2]
-            .parse::<u32>()
-            .map_or(false, |n| n >= 59 && n <= 76),
-        _ => false,
+    let mut count = 0;
+
+    for (line, _, problems) in &passports {
+        let problems = problems.iter().filter(|p| matters(p)).collect::<Vec<_>>();
+
+        if problems.is_empty() {
+            count += 1;
+        } else if show_rejected {
+            println!("Rejected passport on line {}:", line);
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
     }
+
+    Ok(count)
 }
 
-fn valid_hair_color(s: &str) -> bool {
-    s.len() == 7
-        && s.starts_with("#")
-        && s.bytes()
-            .skip(1)
-            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+/// Only the presence of the required fields matters
+pub fn part1(input: &str, show_rejected: bool, format: Option<Format>) -> anyhow::Result<usize> {
+    count_valid(input, show_rejected, format, |p| {
+        matches!(p, Problem::Missing(_))
+    })
 }
 
-fn valid_eye_color(s: &str) -> bool {
-    ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&s)
+pub fn part2(input: &str, show_rejected: bool, format: Option<Format>) -> anyhow::Result<usize> {
+    count_valid(input, show_rejected, format, |_| true)
 }
 
-fn valid_passport_id(s: &str) -> bool {
-    s.len() == 9 && s.bytes().all(|b| matches!(b, b'0'..=b'9'))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "byr:1980 iyr:2012 eyr:2030 hgt:74in hcl:#623a2f ecl:grn pid:087499704";
+
+    /// The problems of the valid passport with one field replaced
+    fn problems(field: &str, value: &str) -> Vec<Problem> {
+        let tokens = VALID
+            .split_whitespace()
+            .map(|token| match token.split_once(':') {
+                Some((f, _)) if f == field => format!("{}:{}", field, value),
+                _ => token.to_owned(),
+            })
+            .collect::<Vec<_>>();
+        let tokens = tokens.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let (passport, mut problems) = Passport::parse(&tokens);
+        problems.extend(schema().validate(&passport));
+        problems
+    }
+
+    fn rejected(field: &str, value: &str, reason: Reason) {
+        assert_eq!(
+            problems(field, value),
+            vec![Problem::Invalid {
+                field: field.to_owned(),
+                value: value.to_owned(),
+                reason,
+            }]
+        );
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(problems("cid", ""), vec![]);
+    }
+
+    #[test]
+    fn every_rule_rejects() {
+        rejected(
+            "byr",
+            "2003",
+            Reason::OutOfRange {
+                min: 1920,
+                max: 2002,
+            },
+        );
+        rejected("byr", "19800", Reason::Digits(4));
+        rejected(
+            "iyr",
+            "2009",
+            Reason::OutOfRange {
+                min: 2010,
+                max: 2020,
+            },
+        );
+        rejected(
+            "eyr",
+            "2031",
+            Reason::OutOfRange {
+                min: 2020,
+                max: 2030,
+            },
+        );
+        rejected("hgt", "194cm", Reason::OutOfRange { min: 150, max: 193 });
+        rejected("hgt", "58in", Reason::OutOfRange { min: 59, max: 76 });
+        rejected(
+            "hcl",
+            "#623a2z",
+            Reason::NoMatch("^(?:#[0-9a-f]{6})$".to_owned()),
+        );
+        rejected("ecl", "wat", Reason::NotOneOf(EYE_COLORS.to_vec()));
+        rejected(
+            "pid",
+            "0874997040",
+            Reason::NoMatch("^(?:[0-9]{9})$".to_owned()),
+        );
+    }
+
+    #[test]
+    fn malformed_values() {
+        rejected("byr", "198O", Reason::NotANumber);
+        rejected(
+            "hgt",
+            "74",
+            Reason::UnknownUnit {
+                unit: String::new(),
+                expected: vec!["cm", "in"],
+            },
+        );
+        rejected("hcl", "623a2f", Reason::Prefix('#'));
+    }
 }
@@ -1,27 +1,253 @@
-use anyhow::Context;
-use itertools::Itertools;
+use anyhow::{bail, Context};
+use thiserror::Error;
 
-fn parse_seat(input: &str) -> usize {
-    input
-        .bytes()
-        .map(|b| matches!(b, b'B' | b'R') as usize)
-        .fold(0, |a, b| (a << 1) + b)
+use std::{collections::HashSet, str::FromStr};
+
+use crate::visualize::{Glyph, Render, Visualizer};
+
+/// Number of bits of a boarding pass that pick the row and the column, which makes the plane
+/// `2^rows` rows of `2^cols` seats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    rows: u32,
+    cols: u32,
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum PassError {
+    #[error("is {found} characters long instead of {expected}")]
+    Length { expected: usize, found: usize },
+
+    #[error("has '{found}' at position {position} instead of {expected}")]
+    Char {
+        found: char,
+        position: usize,
+        expected: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Seat {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Layout {
+    /// Seat ids have to fit in 32 bits
+    pub fn new(rows: u32, cols: u32) -> anyhow::Result<Self> {
+        if rows.checked_add(cols).filter(|&n| n <= 32).is_none() {
+            bail!(
+                "A plane of {} row and {} column bits is too large",
+                rows,
+                cols
+            );
+        }
+
+        Ok(Self { rows, cols })
+    }
+
+    fn len(self) -> usize {
+        (self.rows + self.cols) as usize
+    }
+
+    pub fn id(self, seat: Seat) -> usize {
+        seat.row << self.cols | seat.col
+    }
+
+    pub fn seat(self, id: usize) -> Seat {
+        Seat {
+            row: id >> self.cols,
+            col: id & ((1 << self.cols) - 1),
+        }
+    }
+
+    pub fn decode(self, pass: &str) -> Result<Seat, PassError> {
+        if pass.chars().count() != self.len() {
+            return Err(PassError::Length {
+                expected: self.len(),
+                found: pass.chars().count(),
+            });
+        }
+
+        let mut seat = Seat { row: 0, col: 0 };
+
+        for (i, c) in pass.chars().enumerate() {
+            let (half, bit) = match (i < self.rows as usize, c) {
+                (true, 'F') => (&mut seat.row, 0),
+                (true, 'B') => (&mut seat.row, 1),
+                (false, 'L') => (&mut seat.col, 0),
+                (false, 'R') => (&mut seat.col, 1),
+                (row, found) => {
+                    return Err(PassError::Char {
+                        found,
+                        position: i + 1,
+                        expected: if row { "F or B" } else { "L or R" },
+                    })
+                }
+            };
+
+            *half = *half << 1 | bit;
+        }
+
+        Ok(seat)
+    }
+
+    pub fn encode(self, seat: Seat) -> String {
+        let row = (0..self.rows)
+            .rev()
+            .map(|bit| if seat.row >> bit & 1 == 1 { 'B' } else { 'F' });
+        let col = (0..self.cols)
+            .rev()
+            .map(|bit| if seat.col >> bit & 1 == 1 { 'R' } else { 'L' });
+
+        row.chain(col).collect()
+    }
+}
+
+impl FromStr for Layout {
+    type Err = anyhow::Error;
+
+    /// Parses `rows,cols`, such as `7,3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rows, cols) = s
+            .split_once(',')
+            .with_context(|| format!("Expected rows,cols instead of '{}'", s))?;
+
+        Self::new(
+            rows.trim()
+                .parse()
+                .with_context(|| format!("Invalid number of row bits '{}'", rows))?,
+            cols.trim()
+                .parse()
+                .with_context(|| format!("Invalid number of column bits '{}'", cols))?,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Place {
+    Free,
+    Taken,
+}
+
+impl Glyph for Place {
+    fn glyph(&self) -> char {
+        match self {
+            Place::Free => '.',
+            Place::Taken => '#',
+        }
+    }
+
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            Place::Free => [230, 230, 230],
+            Place::Taken => [60, 120, 220],
+        }
+    }
+}
+
+/// Every seat of the plane, a row of the plane to a row of the grid
+struct SeatMap<'a> {
+    layout: Layout,
+    taken: &'a HashSet<usize>,
+}
+
+impl Render for SeatMap<'_> {
+    type Cell = Place;
+
+    fn size(&self) -> (usize, usize) {
+        (1 << self.layout.rows, 1 << self.layout.cols)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Place {
+        if self.taken.contains(&self.layout.id(Seat { row, col })) {
+            Place::Taken
+        } else {
+            Place::Free
+        }
+    }
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    input
+/// The ids of the seats on the boarding passes, drawing the seat map
+fn seat_ids(
+    input: &str,
+    layout: Layout,
+    visualizer: &mut Visualizer,
+) -> anyhow::Result<Vec<usize>> {
+    let mut ids = input
         .lines()
-        .map(parse_seat)
-        .max()
+        .enumerate()
+        .map(|(i, line)| {
+            layout
+                .decode(line.trim())
+                .map(|seat| layout.id(seat))
+                .with_context(|| format!("Boarding pass on line {} '{}'", i + 1, line))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    ids.sort_unstable();
+
+    let taken = ids.iter().copied().collect();
+    visualizer.frame(&SeatMap {
+        layout,
+        taken: &taken,
+    })?;
+
+    Ok(ids)
+}
+
+pub fn part1(input: &str, layout: Layout, visualizer: &mut Visualizer) -> anyhow::Result<usize> {
+    seat_ids(input, layout, visualizer)?
+        .last()
+        .copied()
         .context("No solution was found")
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    input
-        .lines()
-        .map(parse_seat)
-        .sorted()
-        .tuple_windows()
-        .find_map(|(a, b)| (a + 1 != b).then_some(a + 1))
+/// Your seat is the only free one with both neighbours taken. Prints every free range
+/// between the first and last taken seat if asked to.
+pub fn part2(
+    input: &str,
+    layout: Layout,
+    all: bool,
+    visualizer: &mut Visualizer,
+) -> anyhow::Result<usize> {
+    let ids = seat_ids(input, layout, visualizer)?;
+
+    let gaps = ids
+        .windows(2)
+        .filter(|w| w[1] - w[0] > 1)
+        .map(|w| (w[0] + 1, w[1] - 1))
+        .collect::<Vec<_>>();
+
+    if all {
+        for &(first, last) in &gaps {
+            for id in first..=last {
+                let seat = layout.seat(id);
+                println!(
+                    "free: {} (row {}, column {}) {}",
+                    id,
+                    seat.row,
+                    seat.col,
+                    layout.encode(seat)
+                );
+            }
+        }
+    }
+
+    gaps.iter()
+        .find(|(first, last)| first == last)
+        .map(|&(id, _)| id)
         .context("No solution was found")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_size() {
+        assert!(Layout::new(7, 3).is_ok());
+        assert!(Layout::new(16, 16).is_ok());
+        assert!(Layout::new(30, 3).is_err());
+        assert!("4294967295,1".parse::<Layout>().is_err());
+    }
+}
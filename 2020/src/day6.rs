@@ -1,29 +1,188 @@
-use std::collections::HashSet;
+use anyhow::{bail, Context};
 
-fn group_union(group: &str) -> Option<usize> {
-    group
-        .lines()
-        .map(|line| line.chars().collect::<HashSet<_>>())
-        .reduce(|a, b| a.union(&b).copied().collect())
-        .map(|set| set.len())
+use std::cmp::Reverse;
+
+/// The questions someone answered yes to, bit 0 for `a` up to bit 25 for `z`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Answers(u32);
+
+impl Answers {
+    const ALL: Answers = Answers((1 << 26) - 1);
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        s.chars().try_fold(Self::default(), |answers, c| {
+            if !c.is_ascii_lowercase() {
+                bail!("Invalid question '{}'; must be a to z", c);
+            }
+
+            Ok(Answers(answers.0 | 1 << (c as u8 - b'a')))
+        })
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn contains(self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 >> (question as u8 - b'a') & 1 == 1
+    }
+
+    pub fn questions(self) -> impl Iterator<Item = char> {
+        ('a'..='z').filter(move |&q| self.contains(q))
+    }
+}
+
+/// The answers of each person in a group
+#[derive(Debug, Clone, Default)]
+pub struct Group {
+    people: Vec<Answers>,
 }
 
-fn group_intersection(group: &str) -> Option<usize> {
-    group
-        .lines()
-        .map(|line| line.chars().collect::<HashSet<_>>())
-        .reduce(|a, b| a.intersection(&b).copied().collect())
-        .map(|set| set.len())
+impl Group {
+    /// Questions anyone answered yes to
+    pub fn any(&self) -> Answers {
+        Answers(self.people.iter().fold(0, |acc, a| acc | a.0))
+    }
+
+    /// Questions everyone answered yes to
+    pub fn all(&self) -> Answers {
+        if self.people.is_empty() {
+            return Answers::default();
+        }
+
+        Answers(self.people.iter().fold(Answers::ALL.0, |acc, a| acc & a.0))
+    }
+
+    /// Whether everyone answered yes to exactly the same questions
+    pub fn unanimous(&self) -> bool {
+        self.any() == self.all()
+    }
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let count = input.split("\n\n").filter_map(group_union).sum();
+/// Groups are separated by blank lines
+fn parse_groups(input: &str) -> anyhow::Result<Vec<Group>> {
+    let mut groups = vec![Group::default()];
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            groups.push(Group::default());
+        } else {
+            let answers = Answers::parse(line)
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))?;
+            groups.last_mut().unwrap().people.push(answers);
+        }
+    }
 
-    Ok(count)
+    groups.retain(|group| !group.people.is_empty());
+
+    Ok(groups)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let count = input.split("\n\n").filter_map(group_intersection).sum();
+/// Per question, how many people, groups with anyone and groups with everyone answered yes
+fn report(groups: &[Group]) {
+    let mut counts = ('a'..='z')
+        .map(|q| {
+            let people = groups
+                .iter()
+                .flat_map(|g| &g.people)
+                .filter(|a| a.contains(q))
+                .count();
+            let any = groups.iter().filter(|g| g.any().contains(q)).count();
+            let all = groups.iter().filter(|g| g.all().contains(q)).count();
+
+            (q, people, any, all)
+        })
+        .collect::<Vec<_>>();
+
+    println!("question  people  anyone  everyone");
+    for &(q, people, any, all) in &counts {
+        println!("{:>8}  {:>6}  {:>6}  {:>8}", q, people, any, all);
+    }
+
+    counts.sort_by_key(|&(_, people, _, _)| Reverse(people));
+    if let (Some(most), Some(least)) = (counts.first(), counts.last()) {
+        println!("most common: {} ({} people)", most.0, most.1);
+        println!("least common: {} ({} people)", least.0, least.1);
+    }
+
+    for (i, group) in groups.iter().enumerate().filter(|(_, g)| g.unanimous()) {
+        println!(
+            "group {} ({} people) is unanimous: {}",
+            i + 1,
+            group.people.len(),
+            group.all().questions().collect::<String>()
+        );
+    }
+}
+
+fn count(input: &str, show_report: bool, answers: fn(&Group) -> Answers) -> anyhow::Result<usize> {
+    let groups = parse_groups(input)?;
+
+    if show_report {
+        report(&groups);
+    }
+
+    Ok(groups.iter().map(|g| answers(g).len()).sum())
+}
+
+pub fn part1(input: &str, show_report: bool) -> anyhow::Result<usize> {
+    count(input, show_report, Group::any)
+}
+
+pub fn part2(input: &str, show_report: bool) -> anyhow::Result<usize> {
+    count(input, show_report, Group::all)
+}
+
+#[cfg(test)]
+mod benches {
+    use super::*;
+    use std::collections::HashSet;
+    use test::Bencher;
+
+    const INPUT: &str = include_str!("../input/day6");
+
+    fn hash_set_union(group: &str) -> Option<usize> {
+        group
+            .lines()
+            .map(|line| line.chars().collect::<HashSet<_>>())
+            .reduce(|a, b| a.union(&b).copied().collect())
+            .map(|set| set.len())
+    }
+
+    fn hash_set_intersection(group: &str) -> Option<usize> {
+        group
+            .lines()
+            .map(|line| line.chars().collect::<HashSet<_>>())
+            .reduce(|a, b| a.intersection(&b).copied().collect())
+            .map(|set| set.len())
+    }
+
+    #[bench]
+    fn hash_set(b: &mut Bencher) {
+        b.iter(|| {
+            let any = INPUT
+                .split("\n\n")
+                .filter_map(hash_set_union)
+                .sum::<usize>();
+            let all = INPUT
+                .split("\n\n")
+                .filter_map(hash_set_intersection)
+                .sum::<usize>();
+
+            (any, all)
+        });
+    }
+
+    #[bench]
+    fn bitmask(b: &mut Bencher) {
+        b.iter(|| {
+            let groups = parse_groups(INPUT).unwrap();
+            let any = groups.iter().map(|g| g.any().len()).sum::<usize>();
+            let all = groups.iter().map(|g| g.all().len()).sum::<usize>();
 
-    Ok(count)
+            (any, all)
+        });
+    }
 }
@@ -1,73 +1,309 @@
-use std::collections::HashMap;
-
-type Bag = String;
-type Content = (Bag, usize);
-type Contents = Vec<Content>;
-type Rules = HashMap<Bag, Contents>;
-
-fn parse_rule(rule: &str) -> Option<(Bag, Contents)> {
-    let (key, values) = rule.split_once("bags contain").unwrap();
-
-    let key = key.trim();
-    let values: Contents = values
-        .split(",")
-        .filter_map(|s| {
-            if s.contains("no other bags") {
-                return None;
+use anyhow::{bail, Context};
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write as _,
+    fs,
+    path::Path,
+};
+
+/// Which bags, and how many of each, every bag has to contain
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    contents: BTreeMap<String, Vec<(String, usize)>>,
+}
+
+/// Parses a bag such as `1 bright white bag` or `2 muted yellow bags`
+fn parse_content(s: &str) -> anyhow::Result<(String, usize)> {
+    let s = s.trim();
+    let (count, bag) = s
+        .split_once(' ')
+        .with_context(|| format!("Expected a count and a bag instead of '{}'", s))?;
+    let count = count
+        .parse()
+        .with_context(|| format!("Invalid count '{}'", count))?;
+    let bag = bag
+        .strip_suffix(" bags")
+        .or_else(|| bag.strip_suffix(" bag"))
+        .with_context(|| format!("Expected '{}' to end with bag or bags", bag))?;
+
+    Ok((bag.to_owned(), count))
+}
+
+/// Parses a rule such as `light red bags contain 1 bright white bag, 2 muted yellow bags.`
+fn parse_rule(rule: &str) -> anyhow::Result<(String, Vec<(String, usize)>)> {
+    let (bag, contents) = rule
+        .split_once(" bags contain ")
+        .context("Expected 'bags contain'")?;
+    let contents = contents
+        .trim_end()
+        .strip_suffix('.')
+        .context("Expected the rule to end with '.'")?;
+
+    let contents = if contents == "no other bags" {
+        Vec::new()
+    } else {
+        contents
+            .split(',')
+            .map(parse_content)
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    Ok((bag.trim().to_owned(), contents))
+}
+
+impl Rules {
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let mut rules = Rules::default();
+
+        for (i, line) in input.lines().enumerate() {
+            let (bag, contents) = parse_rule(line)
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))?;
+
+            if rules.contents.insert(bag.clone(), contents).is_some() {
+                bail!("Line {} repeats the rule for {} bags", i + 1, bag);
+            }
+        }
+
+        for (bag, contents) in &rules.contents {
+            if let Some((inner, _)) = contents
+                .iter()
+                .find(|(b, _)| !rules.contents.contains_key(b))
+            {
+                bail!("{} bags contain {} bags, which have no rule", bag, inner);
+            }
+        }
+
+        Ok(rules)
+    }
+
+    fn check_bag(&self, bag: &str) -> anyhow::Result<()> {
+        if !self.contents.contains_key(bag) {
+            bail!("There is no rule for {} bags", bag);
+        }
+
+        Ok(())
+    }
+
+    /// Every bag that eventually contains `bag`, found by walking the rules backwards
+    pub fn containers(&self, bag: &str) -> BTreeSet<&str> {
+        let mut reversed = HashMap::<&str, Vec<&str>>::new();
+        for (outer, contents) in &self.contents {
+            for (inner, _) in contents {
+                reversed.entry(inner).or_default().push(outer);
+            }
+        }
+
+        let mut containers = BTreeSet::new();
+        let mut stack = vec![bag];
+
+        while let Some(bag) = stack.pop() {
+            for &outer in reversed.get(bag).into_iter().flatten() {
+                if containers.insert(outer) {
+                    stack.push(outer);
+                }
+            }
+        }
+
+        containers
+    }
+
+    /// A chain of bags that contains its own first bag, if any
+    pub fn find_cycle(&self) -> Option<Vec<&str>> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Visit {
+            InProgress,
+            Done,
+        }
+
+        fn visit<'a>(
+            rules: &'a Rules,
+            bag: &'a str,
+            visits: &mut HashMap<&'a str, Visit>,
+            path: &mut Vec<&'a str>,
+        ) -> Option<Vec<&'a str>> {
+            match visits.get(bag) {
+                Some(Visit::Done) => return None,
+                Some(Visit::InProgress) => {
+                    let start = path.iter().position(|&b| b == bag).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(bag);
+                    return Some(cycle);
+                }
+                None => {}
+            }
+
+            visits.insert(bag, Visit::InProgress);
+            path.push(bag);
+
+            for (inner, _) in &rules.contents[bag] {
+                if let Some(cycle) = visit(rules, inner, visits, path) {
+                    return Some(cycle);
+                }
+            }
+
+            path.pop();
+            visits.insert(bag, Visit::Done);
+
+            None
+        }
+
+        let mut visits = HashMap::new();
+
+        self.contents
+            .keys()
+            .find_map(|bag| visit(self, bag, &mut visits, &mut Vec::new()))
+    }
+
+    /// How many of each kind of bag `bag` holds, counting bags inside bags
+    pub fn inside(&self, bag: &str) -> anyhow::Result<BTreeMap<&str, usize>> {
+        self.check_bag(bag)?;
+
+        if let Some(cycle) = self.find_cycle() {
+            bail!("The rules are cyclic: {}", cycle.join(" -> "));
+        }
+
+        fn add<'a>(inside: &mut BTreeMap<&'a str, usize>, bag: &'a str, n: usize) -> Option<()> {
+            let total = inside.entry(bag).or_insert(0);
+            *total = total.checked_add(n)?;
+            Some(())
+        }
+
+        fn visit<'a>(
+            rules: &'a Rules,
+            bag: &str,
+            memo: &mut HashMap<&'a str, BTreeMap<&'a str, usize>>,
+        ) -> Option<BTreeMap<&'a str, usize>> {
+            if let Some(inside) = memo.get(bag) {
+                return Some(inside.clone());
+            }
+
+            let (bag, contents) = rules.contents.get_key_value(bag).unwrap();
+            let mut inside = BTreeMap::new();
+
+            for (inner, count) in contents {
+                add(&mut inside, inner, *count)?;
+                for (b, n) in visit(rules, inner, memo)? {
+                    add(&mut inside, b, count.checked_mul(n)?)?;
+                }
             }
 
-            let s = s
-                .trim_end_matches(".")
-                .trim_end_matches("bag")
-                .trim_end_matches("bags")
-                .trim();
+            memo.insert(bag, inside.clone());
+            Some(inside)
+        }
+
+        visit(self, bag, &mut HashMap::new())
+            .with_context(|| format!("{} bags hold too many bags to count", bag))
+    }
 
-            let (v, k) = s
-                .split_once(" ")
-                .map(|(v, k)| (v.parse::<usize>().unwrap_or(0), k))
-                .unwrap();
+    /// The rules as a Graphviz digraph, with an edge from each bag to the bags it contains
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph rules {\n");
 
-            Some((k.to_owned(), v))
-        })
-        .collect();
+        for (bag, contents) in &self.contents {
+            writeln!(dot, "    {:?};", bag).unwrap();
+            for (inner, count) in contents {
+                writeln!(dot, "    {:?} -> {:?} [label={}];", bag, inner, count).unwrap();
+            }
+        }
 
-    Some((key.to_owned(), values))
+        dot.push_str("}\n");
+        dot
+    }
 }
 
-fn contains_shiny_gold(rules: &Rules, contents: &Contents) -> bool {
-    if contents.iter().any(|(bag, _)| bag == "shiny gold") {
-        return true;
+fn parse_and_export(input: &str, dot: Option<&Path>) -> anyhow::Result<Rules> {
+    let rules = Rules::parse(input)?;
+
+    if let Some(path) = dot {
+        fs::write(path, rules.to_dot())
+            .with_context(|| format!("Failed to write rules to '{}'", path.display()))?;
     }
 
-    contents.iter().any(|(bag, _)| {
-        rules.get(bag).map_or(false, |more_contents| {
-            contains_shiny_gold(rules, more_contents)
-        })
-    })
+    Ok(rules)
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let rules: Rules = input.lines().filter_map(parse_rule).collect();
+/// Counts the bags that can contain `bag`, printing them if asked to
+pub fn part1(input: &str, bag: &str, all: bool, dot: Option<&Path>) -> anyhow::Result<usize> {
+    let rules = parse_and_export(input, dot)?;
+    rules.check_bag(bag)?;
+
+    let containers = rules.containers(bag);
 
-    let count = rules
-        .iter()
-        .map(|(_, values)| contains_shiny_gold(&rules, values) as usize)
-        .sum();
+    if all {
+        for container in &containers {
+            println!("{}", container);
+        }
+    }
 
-    Ok(count)
+    Ok(containers.len())
 }
 
-fn count_bags(rules: &Rules, contents: &Contents) -> usize {
-    contents
-        .iter()
-        .map(|(bag, count)| count + count * count_bags(&rules, rules.get(bag).unwrap()))
-        .sum()
+/// Counts the bags inside `bag`, printing how many of each kind if asked to
+pub fn part2(input: &str, bag: &str, all: bool, dot: Option<&Path>) -> anyhow::Result<usize> {
+    let rules = parse_and_export(input, dot)?;
+
+    let inside = rules.inside(bag)?;
+
+    if all {
+        for (inner, count) in &inside {
+            println!("{} {}", count, inner);
+        }
+    }
+
+    inside
+        .values()
+        .try_fold(0usize, |total, &n| total.checked_add(n))
+        .with_context(|| format!("{} bags hold too many bags to count", bag))
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let rules: Rules = input.lines().filter_map(parse_rule).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.
+";
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE, "shiny gold", false, None).unwrap(), 4);
+        assert_eq!(part2(EXAMPLE, "shiny gold", false, None).unwrap(), 32);
+    }
+
+    #[test]
+    fn cycle() {
+        let rules = Rules::parse(
+            "red bags contain 1 blue bag.\n\
+             blue bags contain 2 green bags.\n\
+             green bags contain 1 red bag.\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules.find_cycle().unwrap(),
+            vec!["blue", "green", "red", "blue"]
+        );
+        assert!(rules.inside("red").is_err());
+    }
 
-    let total = count_bags(&rules, rules.get("shiny gold").unwrap());
+    #[test]
+    fn overflow() {
+        // Each level holds a million of the next, so the count passes 2^64 after four levels
+        let rules = (0..5)
+            .map(|i| format!("level{} bags contain 1000000 level{} bags.\n", i, i + 1))
+            .collect::<String>()
+            + "level5 bags contain no other bags.\n";
 
-    Ok(total)
+        assert!(part2(&rules, "level2", false, None).is_ok());
+        assert!(part2(&rules, "level0", false, None).is_err());
+    }
 }
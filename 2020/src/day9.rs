@@ -1,53 +1,151 @@
-use anyhow::bail;
+use anyhow::{bail, Context};
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 
-pub fn part1(input: &str) -> anyhow::Result<isize> {
-    let xs = input
+/// The numbers preceding the one being checked, kept as counts so the window can slide by
+/// adding and removing a single number
+#[derive(Debug, Default)]
+struct Window {
+    counts: HashMap<i64, usize>,
+}
+
+impl Window {
+    fn insert(&mut self, x: i64) {
+        *self.counts.entry(x).or_insert(0) += 1;
+    }
+
+    fn remove(&mut self, x: i64) {
+        if let Some(count) = self.counts.get_mut(&x) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&x);
+            }
+        }
+    }
+
+    /// Whether two different numbers in the window sum to `x`, with a single lookup for each
+    /// distinct number in it. A complement that does not fit in an `i64` cannot be in it.
+    fn has_pair_summing_to(&self, x: i64) -> bool {
+        self.counts.keys().any(|&y| match x.checked_sub(y) {
+            Some(z) => z != y && self.counts.contains_key(&z),
+            None => false,
+        })
+    }
+}
+
+fn parse_numbers(input: &str) -> anyhow::Result<Vec<i64>> {
+    input
         .lines()
-        .map(|line| line.parse::<isize>().expect("Could not parse number"))
-        .collect::<Vec<_>>();
+        .enumerate()
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
+        })
+        .collect()
+}
+
+/// The first number after the preamble that is not the sum of two of the `preamble` numbers
+/// before it
+fn first_invalid(xs: &[i64], preamble: usize) -> anyhow::Result<i64> {
+    if preamble >= xs.len() {
+        bail!(
+            "A preamble of {} leaves none of the {} numbers to check",
+            preamble,
+            xs.len()
+        );
+    }
+    if preamble == 0 {
+        return Ok(xs[0]);
+    }
 
-    for (i, x) in xs.iter().enumerate().skip(25) {
-        let values = xs[i - 25..i].iter().copied().collect::<HashSet<isize>>();
+    let mut window = Window::default();
+    xs.iter().take(preamble).for_each(|&x| window.insert(x));
 
-        if !values.iter().any(|y| values.contains(&(x - y))) {
-            return Ok(*x);
+    for (i, &x) in xs.iter().enumerate().skip(preamble) {
+        if !window.has_pair_summing_to(x) {
+            return Ok(x);
         }
+
+        window.remove(xs[i - preamble]);
+        window.insert(x);
     }
 
-    bail!("No solution was found");
+    bail!("Every number is the sum of two before it")
 }
 
-// 85848519
+/// A contiguous range of at least two numbers that sums to `target`. Growing the range only
+/// ever increases its sum because the numbers are non-negative, so two pointers suffice.
+fn contiguous_range(xs: &[i64], target: i64) -> Option<&[i64]> {
+    let (mut start, mut sum) = (0, 0);
 
-pub fn part2(input: &str) -> anyhow::Result<isize> {
-    let xs = input
-        .lines()
-        .map(|line| line.parse::<isize>().unwrap())
-        .collect::<Vec<_>>();
-    // println!("xs: {:?}", xs);
-
-    for i in 0..xs.len() {
-        for j in (i + 1)..xs.len() {
-            let sum = xs[i..=j].iter().sum::<isize>();
-
-            if sum == 85848519 {
-                let min = xs[i..=j].iter().min().unwrap();
-                let max = xs[i..=j].iter().max().unwrap();
-                println!("min: {:?}, max: {:?}", min, max);
-                println!("sum of both: {:?}", min + max);
-
-                println!("values: {:?}", xs[i..=j].iter().collect::<Vec<_>>());
-
-                // println!("i: {:?} j: {:?} sum: {:?}", i, j, sum);
-                // println!("i: {:?} j: {:?}, added: {:?}", xs[i], xs[j], xs[i] + xs[j]);
-                // println!("we found something...");
-                return Ok(sum);
-            }
+    for (end, &x) in xs.iter().enumerate() {
+        sum += x;
+
+        while sum > target && start < end {
+            sum -= xs[start];
+            start += 1;
+        }
+
+        if sum == target && end > start {
+            return Some(&xs[start..=end]);
         }
     }
 
-    Ok(0)
-    // todo!()
+    None
+}
+
+pub fn part1(input: &str, preamble: usize) -> anyhow::Result<i64> {
+    let xs = parse_numbers(input)?;
+
+    first_invalid(&xs, preamble)
+}
+
+pub fn part2(input: &str, preamble: usize) -> anyhow::Result<i64> {
+    let xs = parse_numbers(input)?;
+
+    if let Some(x) = xs.iter().find(|&&x| x < 0) {
+        bail!(
+            "Ranges can only be searched for non-negative numbers, not {}",
+            x
+        );
+    }
+
+    let invalid = first_invalid(&xs, preamble)?;
+    let range = contiguous_range(&xs, invalid)
+        .with_context(|| format!("No contiguous range sums to {}", invalid))?;
+
+    let min = range.iter().min().unwrap();
+    let max = range.iter().max().unwrap();
+
+    Ok(min + max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n182\n127\n219\n299\n277\n309\n576\n";
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE, 5).unwrap(), 127);
+        assert_eq!(part2(EXAMPLE, 5).unwrap(), 62);
+    }
+
+    #[test]
+    fn large_numbers() {
+        let xs = [i64::MIN, -1, i64::MAX, -2];
+        assert_eq!(first_invalid(&xs, 2).unwrap(), i64::MAX);
+        assert_eq!(
+            first_invalid(&[i64::MAX, 1, -1, i64::MIN], 3).unwrap(),
+            i64::MIN
+        );
+    }
+
+    #[test]
+    fn preamble_too_long() {
+        assert!(part1(EXAMPLE, 20).is_err());
+        assert!(part1(EXAMPLE, 100).is_err());
+    }
 }
@@ -0,0 +1,101 @@
+use std::ops::{Add, AddAssign, Mul, Neg};
+
+/// A position or offset on a grid, with `x` increasing to the east and `y` to the north.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vec2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(self) -> i64 {
+        self.x.abs() + self.y.abs()
+    }
+
+    pub fn rotate_right(self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+
+    pub fn rotate_left(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /// Rotates around the origin by quarter turns, clockwise if positive.
+    pub fn rotate(self, quarter_turns: i64) -> Self {
+        match quarter_turns.rem_euclid(4) {
+            0 => self,
+            1 => self.rotate_right(),
+            2 => -self,
+            _ => self.rotate_left(),
+        }
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, k: i64) -> Self {
+        Self::new(self.x * k, self.y * k)
+    }
+}
+
+/// A compass direction, in clockwise order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Turns by quarter turns, clockwise if positive.
+    pub fn rotate(self, quarter_turns: i64) -> Self {
+        Self::ALL[(self as i64 + quarter_turns).rem_euclid(4) as usize]
+    }
+
+    /// A step of one in this direction.
+    pub fn unit(self) -> Vec2 {
+        match self {
+            Direction::North => Vec2::new(0, 1),
+            Direction::East => Vec2::new(1, 0),
+            Direction::South => Vec2::new(0, -1),
+            Direction::West => Vec2::new(-1, 0),
+        }
+    }
+}
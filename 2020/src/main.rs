@@ -1,8 +1,9 @@
-#![feature(bool_to_option)]
-#![feature(iterator_fold_self)]
-#![feature(str_split_once)]
+#![cfg_attr(test, feature(test))]
 use clap::{crate_authors, Clap};
 
+#[cfg(test)]
+extern crate test;
+
 use std::{
     fs,
     io::{self, Read},
@@ -14,9 +15,15 @@ use std::{
 use anyhow::Context;
 use thiserror::Error;
 
+mod automaton;
 mod day1;
 mod day10;
 mod day11;
+mod day12;
+mod day13;
+mod day14;
+mod day15;
+mod day17;
 mod day18;
 mod day2;
 mod day3;
@@ -25,6 +32,9 @@ mod day5;
 mod day6;
 mod day7;
 mod day9;
+mod geometry;
+mod schema;
+mod visualize;
 // mod day8;
 
 macro_rules! solution_days {
@@ -112,12 +122,14 @@ impl FromStr for SolutionPart {
 
 macro_rules! match_puzzles {
     ($day:expr, $part:expr, $input:expr; $(
-        $Day:ident, $Part:ident;
+        $Day:ident, $Part:ident $(, $arg:expr)*;
     )*) => {{
         #[allow(unreachable_patterns)]
         match ($day, $part) {
             $(
-                (SolutionDay::$Day, SolutionPart::$Part) => println!("{}", $Day::$Part($input)?),
+                (SolutionDay::$Day, SolutionPart::$Part) => {
+                    println!("{}", $Day::$Part($input $(, $arg)*)?)
+                }
             )*
             (day, part) => anyhow::bail!("No solution for {:?}, {:?}", day, part),
         }
@@ -129,15 +141,131 @@ macro_rules! match_puzzles {
 struct Opts {
     #[clap(short, long, about = "Day of puzzle")]
     day: SolutionDay,
-    #[clap(short, long, about = "Part of daily puzzle")]
-    part: SolutionPart,
+    #[clap(
+        short,
+        long,
+        required_unless_present = "repl",
+        about = "Part of daily puzzle"
+    )]
+    part: Option<SolutionPart>,
     #[clap(short, long, about = "Input for puzzle")]
     input: Option<PathBuf>,
+    #[clap(
+        long,
+        about = "Render each generation of grid puzzles: terminal or gif"
+    )]
+    visualize: Option<visualize::Mode>,
+    #[clap(
+        long,
+        default_value = "10",
+        about = "Frames per second of the visualization"
+    )]
+    fps: u32,
+    #[clap(
+        long,
+        default_value = "visualization.gif",
+        about = "Output file of the visualization"
+    )]
+    output: PathBuf,
+    #[clap(
+        long,
+        about = "Start an interactive session, for puzzles that have one"
+    )]
+    repl: bool,
+    #[clap(
+        long,
+        about = "Print rejected records and why, for puzzles that validate records"
+    )]
+    rejected: bool,
+    #[clap(
+        long,
+        about = "Print the parsed records as json or csv, for puzzles that parse records"
+    )]
+    dump: Option<day4::Format>,
+    #[clap(
+        long,
+        about = "Password policy to check instead of the default one of the part, may be repeated: count, position, forbidden:<chars>, distinct:<n> or regex:<pattern>"
+    )]
+    policy: Vec<Box<dyn day2::Policy>>,
+    #[clap(
+        long,
+        default_value = "2020",
+        allow_hyphen_values = true,
+        number_of_values = 1,
+        about = "Sum the expense report entries have to add up to"
+    )]
+    target: i64,
+    #[clap(
+        long,
+        about = "Number of expense report entries to add up, instead of 2 or 3"
+    )]
+    k: Option<usize>,
+    #[clap(long, about = "Print every solution, for puzzles that have several")]
+    all: bool,
+    #[clap(
+        long,
+        default_value = "25",
+        about = "Number of preceding numbers the XMAS cipher checks against"
+    )]
+    preamble: usize,
+    #[clap(
+        long,
+        use_delimiter = true,
+        about = "Joltage gaps adapters may have between them, instead of 1,2,3"
+    )]
+    gaps: Vec<u64>,
+    #[clap(
+        long,
+        allow_hyphen_values = true,
+        number_of_values = 1,
+        about = "Toboggan slope as right,down instead of the ones of the part, may be repeated; right may be a fraction like 1/2 or negative"
+    )]
+    slope: Vec<day3::Slope>,
+    #[clap(
+        long,
+        allow_hyphen_values = true,
+        about = "Search the toboggan slopes up to right,down for the fewest and most trees"
+    )]
+    search: Option<day3::Slope>,
+    #[clap(
+        long,
+        default_value = "7,3",
+        about = "Bits of a boarding pass that pick the row and the column, as rows,cols"
+    )]
+    layout: day5::Layout,
+    #[clap(
+        long,
+        about = "Print statistics of the answers, for puzzles that collect answers"
+    )]
+    report: bool,
+    #[clap(long, default_value = "shiny gold", about = "Bag whose rules to query")]
+    bag: String,
+    #[clap(long, about = "Write the bag rules as a Graphviz graph to this file")]
+    dot: Option<PathBuf>,
+    #[clap(
+        long,
+        about = "Buses in service, such as 17,x,13,19, instead of the ones in the notes"
+    )]
+    buses: Option<day13::Schedule>,
+    #[clap(
+        long,
+        about = "Turn of the memory game to play until, instead of 2020 or 30000000"
+    )]
+    turn: Option<usize>,
 }
 
 fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
 
+    if opts.repl {
+        return match opts.day {
+            SolutionDay::day18 => Ok(day18::repl(io::stdin().lock(), io::stdout())?),
+            day => anyhow::bail!("No interactive session for {:?}", day),
+        };
+    }
+
+    let part = opts.part.context("No part was given")?;
+
     let mut input = String::new();
 
     match opts.input {
@@ -155,31 +283,43 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    let mut visualizer = visualize::Visualizer::new(opts.visualize, opts.fps, opts.output);
+
     match_puzzles! {
-        opts.day, opts.part, &input;
-
-        day1, part1;
-        day1, part2;
-        day2, part1;
-        day2, part2;
-        day3, part1;
-        day3, part2;
-        day4, part1;
-        day4, part2;
-        day5, part1;
-        day5, part2;
-        day6, part1;
-        day6, part2;
-        day7, part1;
-        day7, part2;
+        opts.day, part, &input;
+
+        day1, part1, opts.target, opts.k, opts.all;
+        day1, part2, opts.target, opts.k, opts.all;
+        day2, part1, &opts.policy, opts.rejected;
+        day2, part2, &opts.policy, opts.rejected;
+        day3, part1, &opts.slope, opts.search, &mut visualizer;
+        day3, part2, &opts.slope, &mut visualizer;
+        day4, part1, opts.rejected, opts.dump;
+        day4, part2, opts.rejected, opts.dump;
+        day5, part1, opts.layout, &mut visualizer;
+        day5, part2, opts.layout, opts.all, &mut visualizer;
+        day6, part1, opts.report;
+        day6, part2, opts.report;
+        day7, part1, &opts.bag, opts.all, opts.dot.as_deref();
+        day7, part2, &opts.bag, opts.all, opts.dot.as_deref();
         // day8, part1;
         // day8, part2;
-        day9, part1;
-        day9, part2;
-        day10, part1;
-        day10, part2;
-        day11, part1;
-        day11, part2;
+        day9, part1, opts.preamble;
+        day9, part2, opts.preamble;
+        day10, part1, &opts.gaps;
+        day10, part2, &opts.gaps, opts.all;
+        day11, part1, &mut visualizer;
+        day11, part2, &mut visualizer;
+        day12, part1;
+        day12, part2;
+        day13, part1, opts.buses.as_ref();
+        day13, part2, opts.buses.as_ref();
+        day14, part1;
+        day14, part2;
+        day15, part1, opts.turn;
+        day15, part2, opts.turn;
+        day17, part1;
+        day17, part2;
         day18, part1;
         day18, part2;
     }
@@ -0,0 +1,231 @@
+use regex::Regex;
+use thiserror::Error;
+
+use std::{convert::Infallible, fmt, ops::RangeInclusive};
+
+/// A field value after it has been parsed into its own type, as seen by the rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value<'a> {
+    Number(u32),
+    /// A number with a unit, such as `150cm`
+    Measure(u32, &'a str),
+    Text(&'a str),
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Measure(n, unit) => write!(f, "{}{}", n, unit),
+            Value::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// What a record has for a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry<'a> {
+    Missing,
+    /// The field is present, but its value could not be parsed. The record reports why.
+    Malformed,
+    Value(Value<'a>),
+}
+
+/// A record whose fields can be looked up by name
+pub trait Record {
+    fn get(&self, field: &str) -> Entry<'_>;
+}
+
+/// How the value of a single field is checked
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// Anything goes
+    Any,
+    /// A number within a range, optionally with an exact number of digits
+    Number {
+        digits: Option<usize>,
+        range: RangeInclusive<u32>,
+    },
+    /// A number with one of the units, each with its own range
+    Measure(Vec<(&'static str, RangeInclusive<u32>)>),
+    /// The whole text has to match the pattern
+    Pattern(Regex),
+    /// One of a fixed set of texts
+    OneOf(Vec<&'static str>),
+}
+
+impl Rule {
+    /// A number with exactly four digits within a range
+    pub fn year(range: RangeInclusive<u32>) -> Self {
+        Rule::Number {
+            digits: Some(4),
+            range,
+        }
+    }
+
+    /// Panics if the pattern is not a valid regex, so it is meant for patterns written in code
+    pub fn pattern(pattern: &str) -> Self {
+        Rule::Pattern(Regex::new(&format!("^(?:{})$", pattern)).expect("Invalid pattern"))
+    }
+
+    pub fn check(&self, value: Value) -> Result<(), Reason> {
+        match (self, value) {
+            (Rule::Any, _) => Ok(()),
+            (Rule::Number { digits, range }, Value::Number(n)) => match digits {
+                Some(digits) if n.to_string().len() != *digits => Err(Reason::Digits(*digits)),
+                _ => check_range(n, range),
+            },
+            (Rule::Measure(units), Value::Measure(n, unit)) => {
+                match units.iter().find(|(u, _)| *u == unit) {
+                    Some((_, range)) => check_range(n, range),
+                    None => Err(Reason::UnknownUnit {
+                        unit: unit.to_owned(),
+                        expected: units.iter().map(|(u, _)| *u).collect(),
+                    }),
+                }
+            }
+            (Rule::Pattern(regex), Value::Text(s)) if regex.is_match(s) => Ok(()),
+            (Rule::Pattern(regex), Value::Text(_)) => {
+                Err(Reason::NoMatch(regex.as_str().to_owned()))
+            }
+            (Rule::OneOf(options), Value::Text(s)) if options.contains(&s) => Ok(()),
+            (Rule::OneOf(options), Value::Text(_)) => Err(Reason::NotOneOf(options.clone())),
+            (Rule::Number { .. }, _) => Err(Reason::Type("a number")),
+            (Rule::Measure(_), _) => Err(Reason::Type("a measure")),
+            (Rule::Pattern(_), _) | (Rule::OneOf(_), _) => Err(Reason::Type("text")),
+        }
+    }
+}
+
+fn check_range(n: u32, range: &RangeInclusive<u32>) -> Result<(), Reason> {
+    if range.contains(&n) {
+        Ok(())
+    } else {
+        Err(Reason::OutOfRange {
+            min: *range.start(),
+            max: *range.end(),
+        })
+    }
+}
+
+/// Why a value is not accepted, either while parsing it or by its rule
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    #[error("is not a number")]
+    NotANumber,
+    #[error("does not have {0} digits")]
+    Digits(usize),
+    #[error("is not between {min} and {max}")]
+    OutOfRange { min: u32, max: u32 },
+    #[error("has unit '{unit}' instead of one of {expected:?}")]
+    UnknownUnit {
+        unit: String,
+        expected: Vec<&'static str>,
+    },
+    #[error("does not start with '{0}'")]
+    Prefix(char),
+    #[error("does not match {0}")]
+    NoMatch(String),
+    #[error("is not one of {0:?}")]
+    NotOneOf(Vec<&'static str>),
+    #[error("is not {0}")]
+    Type(&'static str),
+}
+
+/// For fields that are kept as plain strings
+impl From<Infallible> for Reason {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: &'static str,
+    pub required: bool,
+    pub rule: Rule,
+}
+
+/// The fields a record should have and the rules their values have to follow
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn required(mut self, name: &'static str, rule: Rule) -> Self {
+        self.fields.push(Field {
+            name,
+            required: true,
+            rule,
+        });
+        self
+    }
+
+    pub fn optional(mut self, name: &'static str, rule: Rule) -> Self {
+        self.fields.push(Field {
+            name,
+            required: false,
+            rule,
+        });
+        self
+    }
+
+    /// Checks the fields of a record. Problems found while parsing the record, such as
+    /// malformed values, are up to the record to report.
+    pub fn validate(&self, record: &impl Record) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        for field in &self.fields {
+            match record.get(field.name) {
+                Entry::Missing if field.required => problems.push(Problem::Missing(field.name)),
+                Entry::Missing | Entry::Malformed => {}
+                Entry::Value(value) => {
+                    if let Err(reason) = field.rule.check(value) {
+                        problems.push(Problem::Invalid {
+                            field: field.name.to_owned(),
+                            value: value.to_string(),
+                            reason,
+                        });
+                    }
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Missing(&'static str),
+    Duplicate(String),
+    Unknown(String),
+    /// A token that is not of the form `field:value`
+    Malformed(String),
+    Invalid {
+        field: String,
+        value: String,
+        reason: Reason,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Missing(field) => write!(f, "{}: missing", field),
+            Problem::Duplicate(field) => write!(f, "{}: given more than once", field),
+            Problem::Unknown(field) => write!(f, "{}: unknown field", field),
+            Problem::Malformed(token) => write!(f, "'{}': expected field:value", token),
+            Problem::Invalid {
+                field,
+                value,
+                reason,
+            } => write!(f, "{}: '{}' {}", field, value, reason),
+        }
+    }
+}
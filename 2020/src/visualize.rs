@@ -0,0 +1,210 @@
+use std::{
+    convert::TryFrom,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use thiserror::Error;
+
+use crate::automaton::{Backend, Dense, State};
+
+/// Size in pixels of a single cell in exported images
+const CELL_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Terminal,
+    Gif,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ModeError {
+    #[error("{0} is not a visualization mode; must be terminal or gif")]
+    BadMode(String),
+}
+
+impl FromStr for Mode {
+    type Err = ModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "terminal" => Ok(Mode::Terminal),
+            "gif" => Ok(Mode::Gif),
+            s => Err(ModeError::BadMode(s.to_owned())),
+        }
+    }
+}
+
+/// How a single cell is drawn
+pub trait Glyph {
+    fn glyph(&self) -> char;
+    fn colour(&self) -> [u8; 3];
+}
+
+/// A two-dimensional grid that can be drawn cell by cell
+pub trait Render {
+    type Cell: Glyph;
+
+    /// Number of rows and columns
+    fn size(&self) -> (usize, usize);
+    fn cell(&self, row: usize, col: usize) -> Self::Cell;
+}
+
+impl<S: State + Glyph> Render for Dense<S, 2> {
+    type Cell = S;
+
+    fn size(&self) -> (usize, usize) {
+        let [rows, cols] = self.dims();
+        (rows, cols)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> S {
+        self.get([row as i32, col as i32])
+    }
+}
+
+/// Renders successive generations of a grid, or does nothing when no mode was chosen, so
+/// puzzles can report every generation unconditionally.
+pub struct Visualizer {
+    mode: Option<Mode>,
+    fps: u32,
+    output: PathBuf,
+    encoder: Option<gif::Encoder<fs::File>>,
+    /// Rows and columns of the first frame, which every GIF frame has to match
+    size: Option<(usize, usize)>,
+    frame: usize,
+}
+
+impl Visualizer {
+    pub fn new(mode: Option<Mode>, fps: u32, output: PathBuf) -> Self {
+        Self {
+            mode,
+            fps: fps.max(1),
+            output,
+            encoder: None,
+            size: None,
+            frame: 0,
+        }
+    }
+
+    pub fn frame<G: Render>(&mut self, grid: &G) -> anyhow::Result<()> {
+        match self.mode {
+            Some(Mode::Terminal) => self.draw_terminal(grid)?,
+            Some(Mode::Gif) => self.draw_gif(grid)?,
+            None => return Ok(()),
+        }
+
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    fn draw_terminal<G: Render>(&self, grid: &G) -> anyhow::Result<()> {
+        let (rows, cols) = grid.size();
+        let mut out = String::new();
+
+        // Clear the screen once, afterwards only move the cursor back to the top
+        if self.frame == 0 {
+            out.push_str("\x1b[2J");
+        }
+        out.push_str("\x1b[H");
+        out.push_str(&format!("Generation {}\n", self.frame));
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let cell = grid.cell(row, col);
+                let [r, g, b] = cell.colour();
+                out.push_str(&format!("\x1b[38;2;{};{};{}m{}", r, g, b, cell.glyph()));
+            }
+            out.push_str("\x1b[0m\n");
+        }
+
+        let mut stdout = io::stdout();
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()?;
+
+        thread::sleep(Duration::from_secs_f64(1.0 / self.fps as f64));
+
+        Ok(())
+    }
+
+    fn draw_gif<G: Render>(&mut self, grid: &G) -> anyhow::Result<()> {
+        let (rows, cols) = grid.size();
+        let (width, height) = (cols * CELL_SIZE, rows * CELL_SIZE);
+        let too_large = || {
+            anyhow::anyhow!(
+                "A grid of {} by {} cells is too large for a GIF of at most {} by {} cells",
+                rows,
+                cols,
+                u16::MAX as usize / CELL_SIZE,
+                u16::MAX as usize / CELL_SIZE
+            )
+        };
+        let gif_width = u16::try_from(width).map_err(|_| too_large())?;
+        let gif_height = u16::try_from(height).map_err(|_| too_large())?;
+
+        match self.size {
+            Some(size) if size != (rows, cols) => bail!(
+                "Every frame of a GIF needs the same size, but frame {} is {} by {} cells \
+                 instead of {} by {}",
+                self.frame,
+                rows,
+                cols,
+                size.0,
+                size.1
+            ),
+            Some(_) => {}
+            None => {
+                let file = fs::File::create(&self.output).with_context(|| {
+                    format!("Failed to create output file '{}'", self.output.display())
+                })?;
+
+                let mut encoder = gif::Encoder::new(file, gif_width, gif_height, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+
+                self.encoder = Some(encoder);
+                self.size = Some((rows, cols));
+            }
+        }
+
+        let mut palette = Vec::<[u8; 3]>::new();
+        let mut pixels = vec![0; width * height];
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let colour = grid.cell(row, col).colour();
+                let index = match palette.iter().position(|&c| c == colour) {
+                    Some(index) => index,
+                    None if palette.len() < 256 => {
+                        palette.push(colour);
+                        palette.len() - 1
+                    }
+                    None => bail!("A GIF frame can have at most 256 colours"),
+                };
+
+                for y in row * CELL_SIZE..(row + 1) * CELL_SIZE {
+                    let line = y * width;
+                    pixels[line + col * CELL_SIZE..line + (col + 1) * CELL_SIZE]
+                        .iter_mut()
+                        .for_each(|p| *p = index as u8);
+                }
+            }
+        }
+
+        let palette = palette.concat();
+        let mut frame =
+            gif::Frame::from_palette_pixels(gif_width, gif_height, &pixels, &palette, None);
+        frame.delay = (100 / self.fps).max(1) as u16;
+
+        if let Some(encoder) = self.encoder.as_mut() {
+            encoder.write_frame(&frame)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,22 +1,112 @@
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let count = input
-        .lines()
-        .zip(input.lines().skip(1))
-        .filter(|(a, b)| a < b)
-        .count();
-
-    Ok(count)
+use anyhow::{bail, Context};
+
+use std::ops::Range;
+
+/// The depths of a sonar sweep, in the order they were measured
+#[derive(Debug, Clone)]
+pub struct Sonar {
+    depths: Vec<u64>,
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    use itertools::Itertools;
+impl Sonar {
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let depths = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                line.trim()
+                    .parse()
+                    .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { depths })
+    }
+
+    /// How often the sum of `window` depths is larger than that of the window before it.
+    /// Consecutive windows share all but their first and last depth, so it is enough to
+    /// compare depths `window` apart.
+    pub fn increases(&self, window: usize) -> usize {
+        self.depths
+            .iter()
+            .zip(self.depths.iter().skip(window))
+            .filter(|(a, b)| a < b)
+            .count()
+    }
+
+    /// Indices of the longest run of depths that each increase on the one before, the
+    /// earliest one if several are as long
+    pub fn longest_increasing_run(&self) -> Range<usize> {
+        let mut longest = 0..self.depths.len().min(1);
+        let mut start = 0;
+
+        for i in 1..self.depths.len() {
+            if self.depths[i] <= self.depths[i - 1] {
+                start = i;
+            }
+            if i + 1 - start > longest.len() {
+                longest = start..i + 1;
+            }
+        }
+
+        longest
+    }
+
+    /// Indices and sum of the `window` depths with the largest sum, the earliest one if
+    /// several are as deep
+    pub fn deepest_window(&self, window: usize) -> Option<(Range<usize>, u64)> {
+        if window == 0 || window > self.depths.len() {
+            return None;
+        }
+
+        let mut sum = self.depths[..window].iter().sum::<u64>();
+        let mut deepest = (0..window, sum);
+
+        for end in window..self.depths.len() {
+            sum = sum + self.depths[end] - self.depths[end - window];
+            if sum > deepest.1 {
+                deepest = (end + 1 - window..end + 1, sum);
+            }
+        }
 
-    let count = input
-        .lines()
-        .map(|s| s.parse::<u64>().unwrap())
-        .tuple_windows()
-        .filter(|(a, b, c, d)| a + b + c < b + c + d)
-        .count();
+        Some(deepest)
+    }
+}
+
+/// Prints the longest increasing run and the deepest window if asked to
+fn solve(input: &str, window: usize, report: bool) -> anyhow::Result<usize> {
+    if window == 0 {
+        bail!("A window needs at least one depth");
+    }
+
+    let sonar = Sonar::parse(input)?;
+
+    if report {
+        let run = sonar.longest_increasing_run();
+        println!(
+            "longest increasing run: {} depths, lines {} to {}",
+            run.len(),
+            run.start + 1,
+            run.end
+        );
+
+        if let Some((lines, sum)) = sonar.deepest_window(window) {
+            println!(
+                "deepest window: {} deep, lines {} to {}",
+                sum,
+                lines.start + 1,
+                lines.end
+            );
+        }
+    }
+
+    Ok(sonar.increases(window))
+}
+
+pub fn part1(input: &str, window: Option<usize>, report: bool) -> anyhow::Result<usize> {
+    solve(input, window.unwrap_or(1), report)
+}
 
-    Ok(count)
+pub fn part2(input: &str, window: Option<usize>, report: bool) -> anyhow::Result<usize> {
+    solve(input, window.unwrap_or(3), report)
 }
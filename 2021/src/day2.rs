@@ -1,33 +1,256 @@
-pub fn part1(input: &str) -> anyhow::Result<u32> {
-    let (forward, depth) = input
-        .lines()
-        .filter_map(|s| {
-            s.split_once(" ")
-                .map(|(dir, val)| (dir, val.parse::<u32>().unwrap()))
-        })
-        .fold((0, 0), |(x, y), (dir, val)| match dir {
-            "forward" => (x + val, y),
-            "down" => (x, y + val),
-            "up" => (x, y - val),
-            _ => unreachable!(),
-        });
+use anyhow::{bail, Context};
+use thiserror::Error;
+
+use std::{fmt::Write as _, fs, num::ParseIntError, path::Path, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("Expected a command and an amount, such as 'forward 5'")]
+    Format,
+
+    #[error("{0} is not a command; must be forward, down or up")]
+    Unknown(String),
+
+    #[error("Invalid amount: {0}")]
+    Amount(#[from] ParseIntError),
+
+    #[error("Amount {0} is negative")]
+    Negative(i64),
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, amount) = s.trim().split_once(' ').ok_or(CommandError::Format)?;
+        let amount = amount.trim().parse()?;
+        if amount < 0 {
+            return Err(CommandError::Negative(amount));
+        }
+
+        match command {
+            "forward" => Ok(Command::Forward(amount)),
+            "down" => Ok(Command::Down(amount)),
+            "up" => Ok(Command::Up(amount)),
+            command => Err(CommandError::Unknown(command.to_owned())),
+        }
+    }
+}
+
+/// Where the submarine is, with depth increasing downwards. Nothing stops it from rising
+/// above the surface or moving backwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Submarine {
+    pub position: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+/// How the submarine interprets commands
+pub trait Model {
+    fn apply(&self, submarine: &mut Submarine, command: Command) -> anyhow::Result<()>;
+}
+
+fn checked(value: Option<i64>) -> anyhow::Result<i64> {
+    value.context("The submarine moved out of range of a 64 bit integer")
+}
+
+/// Down and up change the depth directly
+#[derive(Debug, Clone, Copy)]
+pub struct Direct;
+
+impl Model for Direct {
+    fn apply(&self, submarine: &mut Submarine, command: Command) -> anyhow::Result<()> {
+        match command {
+            Command::Forward(x) => submarine.position = checked(submarine.position.checked_add(x))?,
+            Command::Down(x) => submarine.depth = checked(submarine.depth.checked_add(x))?,
+            Command::Up(x) => submarine.depth = checked(submarine.depth.checked_sub(x))?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Down and up change the aim, and moving forward dives along it
+#[derive(Debug, Clone, Copy)]
+pub struct Aim;
+
+impl Model for Aim {
+    fn apply(&self, submarine: &mut Submarine, command: Command) -> anyhow::Result<()> {
+        match command {
+            Command::Forward(x) => {
+                submarine.position = checked(submarine.position.checked_add(x))?;
+                let dive = checked(submarine.aim.checked_mul(x))?;
+                submarine.depth = checked(submarine.depth.checked_add(dive))?;
+            }
+            Command::Down(x) => submarine.aim = checked(submarine.aim.checked_add(x))?,
+            Command::Up(x) => submarine.aim = checked(submarine.aim.checked_sub(x))?,
+        }
+
+        Ok(())
+    }
+}
 
-    Ok(forward * depth)
+impl Submarine {
+    /// Every state of the submarine, from the start until after the last command
+    pub fn trajectory(model: &dyn Model, commands: &[Command]) -> anyhow::Result<Vec<Submarine>> {
+        let mut submarine = Submarine::default();
+        let mut trajectory = vec![submarine];
+
+        for (i, &command) in commands.iter().enumerate() {
+            model
+                .apply(&mut submarine, command)
+                .with_context(|| format!("Failed to follow command {}: {:?}", i + 1, command))?;
+            trajectory.push(submarine);
+        }
+
+        Ok(trajectory)
+    }
 }
 
-pub fn part2(input: &str) -> anyhow::Result<u32> {
-    let (forward, depth, _) = input
+fn parse_commands(input: &str) -> anyhow::Result<Vec<Command>> {
+    input
         .lines()
-        .filter_map(|s| {
-            s.split_once(" ")
-                .map(|(dir, val)| (dir, val.parse::<u32>().unwrap()))
+        .enumerate()
+        .map(|(i, line)| {
+            line.parse()
+                .with_context(|| format!("Failed to parse line {}: '{}'", i + 1, line))
         })
-        .fold((0, 0, 0), |(x, y, aim), (dir, val)| match dir {
-            "forward" => (x + val, y + (aim * val), aim),
-            "down" => (x, y, aim + val),
-            "up" => (x, y, aim - val),
-            _ => unreachable!(),
-        });
-
-    Ok(forward * depth)
+        .collect()
+}
+
+fn to_csv(trajectory: &[Submarine]) -> String {
+    let mut csv = String::from("step,position,depth,aim\n");
+
+    for (i, s) in trajectory.iter().enumerate() {
+        writeln!(csv, "{},{},{},{}", i, s.position, s.depth, s.aim).unwrap();
+    }
+
+    csv
+}
+
+/// A line through the positions and depths, with the surface drawn at depth zero
+fn to_svg(trajectory: &[Submarine]) -> String {
+    // Always include the start at the surface
+    let bounds = |coordinate: fn(&Submarine) -> i64| {
+        let values = trajectory.iter().map(coordinate);
+        (
+            values.clone().min().unwrap_or(0).min(0),
+            values.max().unwrap_or(0).max(1),
+        )
+    };
+    let (min_x, max_x) = bounds(|s| s.position);
+    let (min_y, max_y) = bounds(|s| s.depth);
+
+    let points = trajectory
+        .iter()
+        .map(|s| format!("{},{}", s.position, s.depth))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" preserveAspectRatio="none">"#,
+        min_x,
+        min_y,
+        max_x - min_x,
+        max_y - min_y
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"  <line x1="{}" y1="0" x2="{}" y2="0" stroke="steelblue" vector-effect="non-scaling-stroke"/>"#,
+        min_x, max_x
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"  <polyline points="{}" fill="none" stroke="black" stroke-width="2" vector-effect="non-scaling-stroke"/>"#,
+        points
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Follows the commands, writing the trajectory as CSV or SVG, by the file extension, if
+/// asked to
+fn dive(input: &str, model: &dyn Model, export: Option<&Path>) -> anyhow::Result<i64> {
+    let commands = parse_commands(input)?;
+    let trajectory = Submarine::trajectory(model, &commands)?;
+
+    if let Some(path) = export {
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => to_csv(&trajectory),
+            Some("svg") => to_svg(&trajectory),
+            _ => bail!(
+                "Cannot export the trajectory to '{}'; must be a .csv or .svg file",
+                path.display()
+            ),
+        };
+
+        fs::write(path, contents)
+            .with_context(|| format!("Failed to write trajectory to '{}'", path.display()))?;
+    }
+
+    let end = trajectory[trajectory.len() - 1];
+
+    checked(end.position.checked_mul(end.depth))
+}
+
+pub fn part1(input: &str, export: Option<&Path>) -> anyhow::Result<i64> {
+    dive(input, &Direct, export)
+}
+
+pub fn part2(input: &str, export: Option<&Path>) -> anyhow::Result<i64> {
+    dive(input, &Aim, export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2\n";
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "sideways 3".parse::<Command>(),
+            Err(CommandError::Unknown("sideways".to_owned()))
+        );
+        assert_eq!("forward".parse::<Command>(), Err(CommandError::Format));
+        assert!(matches!(
+            "forward five".parse::<Command>(),
+            Err(CommandError::Amount(_))
+        ));
+        assert_eq!(
+            "down -4".parse::<Command>(),
+            Err(CommandError::Negative(-4))
+        );
+        assert_eq!("up 4".parse::<Command>(), Ok(Command::Up(4)));
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE, None).unwrap(), 150);
+        assert_eq!(part2(EXAMPLE, None).unwrap(), 900);
+    }
+
+    #[test]
+    fn overflow() {
+        let input = "forward 3000000000\ndown 4000000000\n";
+        assert!(part1(input, None).is_err());
+
+        let input = "down 9223372036854775807\ndown 1\n";
+        assert!(part2(input, None).is_err());
+    }
 }
@@ -108,12 +108,14 @@ impl FromStr for SolutionPart {
 
 macro_rules! match_puzzles {
     ($day:expr, $part:expr, $input:expr; $(
-        $Day:ident, $Part:ident;
+        $Day:ident, $Part:ident $(, $arg:expr)*;
     )*) => {{
         #[allow(unreachable_patterns)]
         match ($day, $part) {
             $(
-                (SolutionDay::$Day, SolutionPart::$Part) => println!("{}", $Day::$Part($input)?),
+                (SolutionDay::$Day, SolutionPart::$Part) => {
+                    println!("{}", $Day::$Part($input $(, $arg)*)?)
+                }
             )*
             (day, part) => anyhow::bail!("No solution for {:?}, {:?}", day, part),
         }
@@ -129,9 +131,23 @@ struct Opts {
     part: SolutionPart,
     #[clap(short, long, about = "Input for puzzle")]
     input: Option<PathBuf>,
+    #[clap(
+        long,
+        about = "Number of depths in a sliding window, instead of 1 or 3"
+    )]
+    window: Option<usize>,
+    #[clap(
+        long,
+        about = "Print statistics of the input, for puzzles that have them"
+    )]
+    report: bool,
+    #[clap(
+        long,
+        about = "Write the trajectory of the submarine to this .csv or .svg file"
+    )]
+    trajectory: Option<PathBuf>,
 }
 
-
 fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
 
@@ -155,10 +171,10 @@ fn main() -> anyhow::Result<()> {
     match_puzzles! {
         opts.day, opts.part, &input;
 
-        day1, part1;
-        day1, part2;
-        day2, part1;
-        day2, part2;
+        day1, part1, opts.window, opts.report;
+        day1, part2, opts.window, opts.report;
+        day2, part1, opts.trajectory.as_deref();
+        day2, part2, opts.trajectory.as_deref();
         // day3, part1;
         // day3, part2;
         // day4, part1;